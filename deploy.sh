@@ -1,2 +1,23 @@
 bash build.sh
-near deploy contract.msolomodenko.testnet ./res/auction.wasm 
\ No newline at end of file
+
+CONTRACT=contract.msolomodenko.testnet
+MASTER=msolomodenko.testnet
+
+# The contract state is not migrated when its storage layout changes (lot ids derived from the
+# supplier, round and nonce, escrow balances, round archive), so such a version needs a fresh
+# account. Pass --fresh to delete the old account with its state and balance, create it again
+# and initialize it. By default only the code is updated
+if [ "$1" == "--fresh" ]; then
+    echo "This deletes $CONTRACT with all lots, escrow balances and archived auctions,"
+    echo "and sends its balance to $MASTER"
+    read -r -p "Type the contract name to continue: " CONFIRM
+    if [ "$CONFIRM" != "$CONTRACT" ]; then
+        echo "Aborted"
+        exit 1
+    fi
+    near delete $CONTRACT $MASTER
+    near create-account $CONTRACT --masterAccount $MASTER --initialBalance 10
+    near deploy $CONTRACT ./res/auction.wasm --initFunction new --initArgs '{}'
+else
+    near deploy $CONTRACT ./res/auction.wasm
+fi
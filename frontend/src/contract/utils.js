@@ -17,7 +17,7 @@ export async function initContract() {
 
     window.contract = new Contract(window.walletConnection.account(), nearConfig.contractName, {
         viewMethods: ['get_lots', 'get_items', 'get_auction_state'],
-        changeMethods: ['add_item_to_auction', 'produce_auction', 'make_bid', 'start_new_auction']
+        changeMethods: ['add_item_to_auction', 'produce_auction', 'make_bid', 'start_new_auction', 'withdraw']
     })
 }
 
@@ -40,18 +40,17 @@ export async function isAuctionOpen() {
 
 export async function getLots() {
     let lots = await window.contract.get_lots({ args: {} }).catch(errorHandler)
-    if (lots === null || lots === undefined || lots === '') {
+    if (lots === null || lots === undefined) {
         return
     }
 
     try {
-        lots = JSON.parse(lots)
         let suitableLots = []
         lots.forEach(lot => {
             suitableLots.push({
                 is_owner: true,
                 item: lot.item,
-                current_bid: lot.current_bid.toLocaleString('fullwide', { useGrouping: false }),
+                current_bid: lot.current_bid,
                 are_u_winner: getAccountId() === lot.winner,
                 are_u_supplier: getAccountId() === lot.supplier,
                 item_hash: lot.item_hash
@@ -74,8 +73,16 @@ export async function produceAuction() {
     await window.contract.produce_auction({ args: {} }).catch(errorHandler)
 }
 
-export async function makeBid(itemHash, attachedDeposit) {
-    await window.contract.make_bid({ item_hash: itemHash }, DEFAULT_FUNCTION_CALL_GAS, attachedDeposit).catch(errorHandler)
+// the first bid also pays for storage of the bidder's balance, the rest stays in the balance
+const BALANCE_STORAGE = BigInt('10000000000000000000000') // 0.01 NEAR
+
+export async function makeBid(itemHash, amount) {
+    const attachedDeposit = (BigInt(amount) + BALANCE_STORAGE).toString()
+    await window.contract.make_bid({ item_hash: itemHash, amount: amount }, DEFAULT_FUNCTION_CALL_GAS, attachedDeposit).catch(errorHandler)
+}
+
+export async function withdraw() {
+    await window.contract.withdraw({}).catch(errorHandler)
 }
 
 export async function startNewAuction() {
@@ -0,0 +1,144 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::UnorderedSet;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::errors::AuctionError;
+use crate::roles::Role;
+use crate::{Auction, AuctionExt, StorageKey};
+
+pub const INVITE_CODE_HASH_LENGTH: usize = 32;
+
+/// Who can take part in the current auction
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AccessPolicy {
+    Open,      // everybody
+    Allowlist, // only accounts that have been added by an admin
+    // accounts that have presented a code with such SHA256 hash join the allowlist
+    InviteCode { code_hash: Base64VecU8 },
+}
+
+#[near_bindgen]
+impl Auction {
+    /// set who can take part in the current auction. The policy and the allowlist are reset
+    /// to `Open` when the auction is finished
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - who can make bids
+    /// * `restrict_suppliers` - if true, the policy is checked when items are added too
+    ///
+    /// # Panics
+    ///
+    /// * caller must be an admin
+    /// * invite code hash is not 32 bytes long
+    pub fn set_access_policy(&mut self, policy: AccessPolicy, restrict_suppliers: bool) {
+        self.assert_role(&[Role::Admin]);
+
+        if let AccessPolicy::InviteCode { code_hash } = &policy {
+            if code_hash.0.len() != INVITE_CODE_HASH_LENGTH {
+                AuctionError::InvalidInviteCodeHash.panic()
+            }
+        }
+
+        self.access_policy = policy;
+        self.restrict_suppliers = restrict_suppliers;
+    }
+
+    pub fn get_access_policy(&self) -> AccessPolicy {
+        self.access_policy.clone()
+    }
+
+    /// # Panics
+    ///
+    /// * caller must be an admin
+    pub fn add_to_allowlist(&mut self, account_ids: Vec<AccountId>) {
+        self.assert_role(&[Role::Admin]);
+
+        for account_id in account_ids.iter() {
+            self.allowlist.insert(account_id);
+        }
+    }
+
+    /// # Panics
+    ///
+    /// * caller must be an admin
+    pub fn remove_from_allowlist(&mut self, account_ids: Vec<AccountId>) {
+        self.assert_role(&[Role::Admin]);
+
+        for account_id in account_ids.iter() {
+            self.allowlist.remove(account_id);
+        }
+    }
+
+    /// present an invite code to join the current auction
+    ///
+    /// # Arguments
+    ///
+    /// * `invite_code` - preimage of the hash from the access policy
+    ///
+    /// # Panics
+    ///
+    /// * contract is paused
+    /// * auction does not use invite codes
+    /// * invite code is wrong
+    pub fn join_auction(&mut self, invite_code: String) {
+        self.assert_not_paused();
+        match &self.access_policy {
+            AccessPolicy::InviteCode { code_hash } => {
+                if env::sha256(invite_code.as_bytes()) != code_hash.0 {
+                    AuctionError::InvalidInviteCode.panic()
+                }
+            }
+            _ => AuctionError::InviteCodesNotUsed.panic(),
+        }
+
+        self.allowlist.insert(&env::predecessor_account_id());
+    }
+
+    /// check if the account can take part in the current auction
+    pub fn is_allowed(&self, account_id: AccountId) -> bool {
+        match self.access_policy {
+            AccessPolicy::Open => true,
+            AccessPolicy::Allowlist | AccessPolicy::InviteCode { .. } => {
+                self.allowlist.contains(&account_id)
+            }
+        }
+    }
+}
+
+impl Auction {
+    /// # Arguments
+    ///
+    /// * `account_id` - bidder or supplier
+    /// * `is_supplier` - suppliers are checked only if the policy restricts them
+    ///
+    /// # Panics
+    ///
+    /// * account is not allowed to take part in the current auction
+    pub(crate) fn assert_allowed(&self, account_id: &AccountId, is_supplier: bool) {
+        if is_supplier && !self.restrict_suppliers {
+            return;
+        }
+
+        if !self.is_allowed(account_id.clone()) {
+            AuctionError::AccountNotAllowed {
+                account_id: account_id.clone(),
+            }
+            .panic()
+        }
+    }
+
+    /// make the next auction open for everybody. The next auction gets an allowlist with its own
+    /// prefix, so the finished one is dropped without iterating over its accounts. Cancellation
+    /// has already removed them in batches (see `shrink_allowlist`)
+    pub(crate) fn reset_access_policy(&mut self) {
+        self.access_policy = AccessPolicy::Open;
+        self.restrict_suppliers = false;
+        self.allowlist = UnorderedSet::new(StorageKey::Allowlist {
+            round: self.round + 1,
+        });
+    }
+}
@@ -0,0 +1,334 @@
+use std::collections::HashSet;
+
+use crate::{ItemHash, Money};
+
+/// max amount of candidates that are allocated exactly
+pub const EXACT_ALLOCATION_LIMIT: usize = 16;
+
+/// Bid for a set of lots that has to be accepted or rejected as a whole
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub items: Vec<ItemHash>,
+    pub amount: Money,
+}
+
+/// pick candidates with disjoint lots and the biggest total amount. Every bid is a candidate
+/// that wants a set of lots for a price, all or nothing, so a bid for a single lot is a candidate
+/// with one lot.
+///
+/// The problem is NP-hard, so it is solved exactly only when there are at most
+/// `EXACT_ALLOCATION_LIMIT` candidates. Otherwise candidates are sorted by `amount / sqrt(lots)`
+/// and accepted while they do not conflict with already accepted ones. Such greedy allocation
+/// gets at least `1 / sqrt(m)` of the optimal revenue, where `m` is the number of distinct lots
+/// (Lehmann, O'Callaghan, Shoham, "Truth revelation in approximately efficient combinatorial
+/// auctions", 2002)
+///
+/// # Arguments
+///
+/// * `candidates` - bids for single lots and bundles
+///
+/// return indices of accepted candidates in ascending order
+pub fn allocate(candidates: &[Candidate]) -> Vec<usize> {
+    let mut accepted = if candidates.len() <= EXACT_ALLOCATION_LIMIT {
+        allocate_exactly(candidates)
+    } else {
+        allocate_greedily(candidates)
+    };
+
+    accepted.sort_unstable();
+    accepted
+}
+
+/// split the price of a bundle between its lots in proportion to their min bids.
+/// The rounding remainder goes to the last lot
+///
+/// # Arguments
+///
+/// * `amount` - price of the bundle
+/// * `min_bids` - min bids of the bundle's lots
+pub fn split_price(amount: Money, min_bids: &[Money]) -> Vec<Money> {
+    let total: Money = min_bids.iter().sum();
+    let mut rest = amount;
+
+    let mut prices: Vec<Money> = min_bids
+        .iter()
+        .map(|min_bid| {
+            // amount * min_bid can overflow u128, so the whole part of amount / total is
+            // multiplied directly and only the remainder needs the wide multiplication
+            let price = match amount.checked_div(total) {
+                Some(whole) => whole * min_bid + mul_div(amount % total, *min_bid, total),
+                None => 0,
+            };
+
+            rest -= price;
+            price
+        })
+        .collect();
+
+    if let Some(last) = prices.last_mut() {
+        *last += rest;
+    }
+
+    prices
+}
+
+fn allocate_exactly(candidates: &[Candidate]) -> Vec<usize> {
+    // visiting big bids first makes the bound prune more branches
+    let mut order: Vec<usize> = (0..candidates.len()).collect();
+    order.sort_by(|a, b| candidates[*b].amount.cmp(&candidates[*a].amount));
+
+    // sum of amounts of the candidates that have not been visited yet
+    let mut rest_amounts = vec![0; order.len() + 1];
+    for position in (0..order.len()).rev() {
+        rest_amounts[position] = rest_amounts[position + 1] + candidates[order[position]].amount;
+    }
+
+    let mut search = ExactSearch {
+        candidates,
+        order: &order,
+        rest_amounts: &rest_amounts,
+        taken_items: HashSet::new(),
+        current: vec![],
+        current_amount: 0,
+        best: vec![],
+        best_amount: 0,
+    };
+
+    search.visit(0);
+    search.best
+}
+
+struct ExactSearch<'a> {
+    candidates: &'a [Candidate],
+    order: &'a [usize],
+    rest_amounts: &'a [Money],
+    taken_items: HashSet<&'a ItemHash>,
+    current: Vec<usize>,
+    current_amount: Money,
+    best: Vec<usize>,
+    best_amount: Money,
+}
+
+impl<'a> ExactSearch<'a> {
+    fn visit(&mut self, position: usize) {
+        if self.current_amount > self.best_amount {
+            self.best = self.current.clone();
+            self.best_amount = self.current_amount;
+        }
+
+        if position == self.order.len()
+            || self.current_amount + self.rest_amounts[position] <= self.best_amount
+        {
+            return;
+        }
+
+        let index = self.order[position];
+        let candidate = &self.candidates[index];
+
+        if !conflicts(candidate, &self.taken_items) {
+            for item in candidate.items.iter() {
+                self.taken_items.insert(item);
+            }
+            self.current.push(index);
+            self.current_amount += candidate.amount;
+
+            self.visit(position + 1);
+
+            self.current_amount -= candidate.amount;
+            self.current.pop();
+            for item in candidate.items.iter() {
+                self.taken_items.remove(item);
+            }
+        }
+
+        self.visit(position + 1);
+    }
+}
+
+fn allocate_greedily(candidates: &[Candidate]) -> Vec<usize> {
+    let score = |candidate: &Candidate| {
+        candidate.amount as f64 / (candidate.items.len().max(1) as f64).sqrt()
+    };
+
+    let mut order: Vec<usize> = (0..candidates.len()).collect();
+    // stable sort keeps earlier candidates first when scores are equal
+    order.sort_by(|a, b| {
+        score(&candidates[*b])
+            .partial_cmp(&score(&candidates[*a]))
+            .unwrap()
+    });
+
+    let mut taken_items = HashSet::new();
+    let mut accepted = vec![];
+
+    for index in order {
+        let candidate = &candidates[index];
+
+        if !conflicts(candidate, &taken_items) {
+            taken_items.extend(candidate.items.iter());
+            accepted.push(index);
+        }
+    }
+
+    accepted
+}
+
+/// return `a * b / c` rounded down without overflowing on the product. The product is built
+/// bit by bit of `b` while the quotient and the remainder modulo `c` are kept
+///
+/// `a` must be less than `c` and `b` must not exceed `c`, so the result fits into `Money`
+fn mul_div(a: Money, b: Money, c: Money) -> Money {
+    let mut quotient = 0;
+    let mut remainder = 0;
+
+    for bit in (0..Money::BITS).rev() {
+        quotient *= 2;
+        remainder = add_modulo(remainder, remainder, c, &mut quotient);
+
+        if b >> bit & 1 == 1 {
+            remainder = add_modulo(remainder, a, c, &mut quotient);
+        }
+    }
+
+    quotient
+}
+
+/// return `(x + y) mod c` for `x` and `y` less than `c` and count the overflow of `c` in
+/// `quotient`
+fn add_modulo(x: Money, y: Money, c: Money, quotient: &mut Money) -> Money {
+    let (sum, overflow) = x.overflowing_add(y);
+
+    if overflow || sum >= c {
+        *quotient += 1;
+        // the real sum is less than 2c, so the wrapped difference is exact
+        sum.wrapping_sub(c)
+    } else {
+        sum
+    }
+}
+
+fn conflicts(candidate: &Candidate, taken_items: &HashSet<&ItemHash>) -> bool {
+    candidate
+        .items
+        .iter()
+        .any(|item| taken_items.contains(item))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(items: &[&str], amount: Money) -> Candidate {
+        Candidate {
+            items: items.iter().map(|item| item.to_string()).collect(),
+            amount,
+        }
+    }
+
+    fn revenue(candidates: &[Candidate], accepted: &[usize]) -> Money {
+        accepted.iter().map(|index| candidates[*index].amount).sum()
+    }
+
+    fn assert_disjoint(candidates: &[Candidate], accepted: &[usize]) {
+        let mut items = HashSet::new();
+
+        for index in accepted {
+            for item in candidates[*index].items.iter() {
+                assert!(items.insert(item), "item {} is allocated twice", item);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bundle_beats_single_bids() {
+        let candidates = vec![
+            candidate(&["a"], 10),
+            candidate(&["b"], 10),
+            candidate(&["a", "b"], 25),
+        ];
+
+        assert_eq!(allocate(&candidates), vec![2]);
+    }
+
+    #[test]
+    fn test_single_bids_beat_bundle() {
+        let candidates = vec![
+            candidate(&["a"], 10),
+            candidate(&["b"], 20),
+            candidate(&["a", "b"], 25),
+        ];
+
+        assert_eq!(allocate(&candidates), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_exact_allocation_is_not_greedy() {
+        // the biggest bid blocks two bundles that pay more together
+        let candidates = vec![
+            candidate(&["a", "b", "c"], 30),
+            candidate(&["a", "b"], 20),
+            candidate(&["c", "d"], 20),
+        ];
+
+        assert_eq!(allocate(&candidates), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_empty_allocation() {
+        assert!(allocate(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_greedy_allocation() {
+        let mut candidates: Vec<Candidate> = (0..EXACT_ALLOCATION_LIMIT as u128 * 2)
+            .map(|index| candidate(&[&format!("item_{}", index % 10)], index + 1))
+            .collect();
+        candidates.push(candidate(&["item_0", "item_1", "item_2", "item_3"], 200));
+
+        let accepted = allocate(&candidates);
+        assert_disjoint(&candidates, &accepted);
+
+        // the bundle scores 200 / sqrt(4) = 100 and beats every single bid
+        assert!(accepted.contains(&(candidates.len() - 1)));
+
+        // the best single bids for each of 10 items pay 275 in total, so the optimum is not less
+        let bound = 275f64 / (10f64).sqrt();
+        assert!(revenue(&candidates, &accepted) as f64 >= bound);
+    }
+
+    #[test]
+    fn test_split_price() {
+        assert_eq!(split_price(100, &[1, 1, 2]), vec![25, 25, 50]);
+        assert_eq!(split_price(10, &[1, 1, 1]), vec![3, 3, 4]);
+        assert_eq!(split_price(10, &[0, 0]), vec![0, 10]);
+    }
+
+    #[test]
+    fn test_split_big_price() {
+        let near = 10u128.pow(24);
+
+        // floats lose the last digits of such prices
+        let prices = split_price(1000 * near + 1, &[near, 2 * near]);
+        assert_eq!(
+            prices,
+            vec![
+                333_333_333_333_333_333_333_333_333,
+                666_666_666_666_666_666_666_666_668
+            ]
+        );
+        assert_eq!(prices.iter().sum::<Money>(), 1000 * near + 1);
+
+        let prices = split_price(Money::MAX, &[Money::MAX / 2, Money::MAX / 2]);
+        assert_eq!(prices, vec![Money::MAX / 2, Money::MAX / 2 + 1]);
+    }
+
+    #[test]
+    fn test_mul_div() {
+        assert_eq!(mul_div(7, 3, 10), 2);
+        assert_eq!(mul_div(0, 5, 10), 0);
+        assert_eq!(
+            mul_div(Money::MAX - 1, Money::MAX - 1, Money::MAX),
+            Money::MAX - 2
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use near_sdk::json_types::U128;
+use near_sdk::{near_bindgen, AccountId, Promise};
+
+use crate::roles::Role;
+use crate::{Auction, AuctionExt, Money};
+
+#[near_bindgen]
+impl Auction {
+    /// set deposit that suppliers have to attach to `add_item_to_auction`. Lots that have
+    /// already been listed keep their bonds
+    ///
+    /// # Arguments
+    ///
+    /// * `bond` - deposit for one lot. 0 disables bonds
+    ///
+    /// # Panics
+    ///
+    /// * caller must be an admin
+    pub fn set_listing_bond(&mut self, bond: U128) {
+        self.assert_role(&[Role::Admin]);
+        self.listing_bond = bond.0;
+    }
+
+    pub fn get_listing_bond(&self) -> U128 {
+        U128(self.listing_bond)
+    }
+}
+
+impl Auction {
+    /// send the bond back to the supplier
+    pub(crate) fn return_bond(&mut self, supplier: &AccountId, bond: Money) {
+        if bond > 0 {
+            self.bonds_total -= bond;
+            Promise::new(supplier.clone()).transfer(bond);
+        }
+    }
+
+    /// send the bond of a delisted lot to the owner. It is counted as fees of the current auction
+    pub(crate) fn forfeit_bond(&mut self, bond: Money) {
+        if bond > 0 {
+            self.bonds_total -= bond;
+            self.forfeited_bonds += bond;
+            Promise::new(self.owner_id.clone()).transfer(bond);
+        }
+    }
+
+    /// send bonds of the lots that are left in the current auction back to their suppliers
+    pub(crate) fn return_unsold_bonds(&mut self) {
+        for (supplier_id, bond) in self.lot_bonds() {
+            self.return_bond(&supplier_id, bond);
+        }
+    }
+
+    /// return supplier and bond of each lot in the current auction
+    fn lot_bonds(&self) -> Vec<(AccountId, Money)> {
+        self.lots
+            .iter()
+            .map(|(item_hash, supplier_id)| {
+                let bond = self
+                    .suppliers
+                    .get(&supplier_id)
+                    .and_then(|supplier| supplier.items.get(&item_hash))
+                    .map_or(0, |item| item.bond);
+
+                (supplier_id, bond)
+            })
+            .collect()
+    }
+}
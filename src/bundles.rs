@@ -0,0 +1,491 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::allocation::{self, Candidate};
+use crate::errors::AuctionError;
+use crate::history::{BidKind, BidStatus};
+use crate::views::BundleBidView;
+use crate::{Auction, AuctionExt, Bid, ItemHash, Money, DEFAULT_PAGE_LIMIT};
+
+/// Bid for several lots that are bought together or not bought at all
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct BundleBid {
+    pub account_id: AccountId,
+    pub items: Vec<ItemHash>,
+    pub amount: Money, // locked in the bidder's balance until the auction is finished
+    pub created_at: u64,
+    pub records: Vec<u64>, // index of the bid in the history of each lot
+}
+
+#[near_bindgen]
+impl Auction {
+    /// bid for several lots together. Bundle bids are not outbid, they compete with bids for
+    /// single lots and other bundles when the auction is finished. The combination of bids with
+    /// the biggest revenue wins (see `allocation::allocate`)
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - ids of the lots (see `supplier::get_lot_id`)
+    /// * `amount` - bid for all lots. Attached deposit is added to caller's free balance before
+    /// the bid is locked
+    ///
+    /// return index of the bundle bid in the current auction
+    ///
+    /// # Panics
+    ///
+    ///  * bundle has less than 2 lots or contains the same lot twice
+    ///  * lot does not exist, belongs to the caller or has several units
+    ///  * amount is less than the sum of min bids of the lots
+    ///  * free balance must cover the bid
+    ///  * attached deposit must cover storage of a new balance (see `deposit`)
+    ///  * caller can not bid (see `make_bid`)
+    #[payable]
+    pub fn make_bundle_bid(&mut self, items: Vec<ItemHash>, amount: U128) -> U64 {
+        let bidder = env::predecessor_account_id();
+        self.assert_can_bid(&bidder);
+
+        if items.len() < 2 {
+            AuctionError::BundleTooSmall.panic()
+        }
+
+        self.deposit_attached(&bidder);
+
+        let mut min_bids: Money = 0;
+
+        for (index, item_hash) in items.iter().enumerate() {
+            if items[..index].contains(item_hash) {
+                AuctionError::DuplicateItem {
+                    item_hash: item_hash.clone(),
+                }
+                .panic()
+            }
+
+            let supplier_id = self.lots.get(item_hash).unwrap_or_else(|| {
+                AuctionError::ItemNotFound {
+                    item_hash: item_hash.clone(),
+                }
+                .panic()
+            });
+
+            if supplier_id == bidder {
+                AuctionError::OwnItem.panic()
+            }
+
+            let mut supplier = self.suppliers.get(&supplier_id).unwrap();
+            let item = supplier.items.get(item_hash).unwrap();
+
+            if item.quantity != 1 {
+                AuctionError::MultiUnitBundleItem {
+                    item_hash: item_hash.clone(),
+                }
+                .panic()
+            }
+
+            min_bids += item.min_bid;
+            supplier.register_bid(item_hash);
+        }
+
+        if amount.0 < min_bids {
+            AuctionError::BundleBelowMinBids {
+                min_bids: U128(min_bids),
+                amount,
+            }
+            .panic()
+        }
+
+        self.lock(&bidder, amount.0);
+
+        let records = items
+            .iter()
+            .map(|item_hash| self.record_pending_bid(&bidder, item_hash, amount.0, BidKind::Bundle))
+            .collect();
+
+        self.bundle_bids.push(&BundleBid {
+            account_id: bidder,
+            items,
+            amount: amount.0,
+            created_at: env::block_timestamp(),
+            records,
+        });
+
+        U64(self.bundle_bids.len() - 1)
+    }
+
+    /// return bundle bids of the current auction, oldest first
+    ///
+    /// # Arguments
+    ///
+    /// * `from_index` - index of the first bundle bid. 0 if not set
+    /// * `limit` - max amount of bundle bids. `DEFAULT_PAGE_LIMIT` if not set
+    pub fn get_bundle_bids(
+        &self,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<BundleBidView> {
+        self.bundle_bids
+            .iter()
+            .skip(from_index.map_or(0, |index| index.0) as usize)
+            .take(limit.map_or(DEFAULT_PAGE_LIMIT, |limit| limit.0) as usize)
+            .map(BundleBidView::from)
+            .collect()
+    }
+}
+
+impl Auction {
+    /// remove bundle bids that have been made after the close time of a candle auction and
+    /// unlock their money
+    ///
+    /// # Arguments
+    ///
+    /// * `close_time` - moment when the candle auction has been closed
+    pub(crate) fn drop_bundle_bids_after(&mut self, close_time: u64) {
+        let bundle_bids = self.bundle_bids.to_vec();
+
+        if bundle_bids
+            .iter()
+            .all(|bundle| bundle.created_at <= close_time)
+        {
+            return;
+        }
+
+        self.bundle_bids.clear();
+
+        for bundle in bundle_bids {
+            if bundle.created_at <= close_time {
+                self.bundle_bids.push(&bundle);
+            } else {
+                self.refund_bundle_bid(&bundle);
+            }
+        }
+    }
+
+    /// pick the combination of single and bundle bids with the biggest revenue. Bids that have
+    /// lost are unlocked in their bidders' balances and bid history of the lots is settled
+    ///
+    /// return winner and price of each sold lot. Price of a bundle is split between its lots
+    /// (see `allocation::split_price`)
+    pub(crate) fn determine_winners(&mut self) -> Vec<(ItemHash, Bid)> {
+        let bids: Vec<(ItemHash, Bid)> = self.items_and_bids.iter().collect();
+        let bundle_bids = self.bundle_bids.to_vec();
+        let (won_bids, won_bundles) = self.allocate_bids(&bids, &bundle_bids);
+
+        let mut winners = vec![];
+
+        for ((item_hash, bid), won) in bids.into_iter().zip(won_bids) {
+            if won {
+                self.settle_bid_history(&item_hash, BidStatus::Won);
+                winners.push((item_hash, bid));
+            } else {
+                self.settle_bid_history(&item_hash, BidStatus::Refunded);
+                self.unlock(&bid.account_id, bid.bid);
+            }
+        }
+
+        for (bundle, won) in bundle_bids.into_iter().zip(won_bundles) {
+            if !won {
+                self.refund_bundle_bid(&bundle);
+                continue;
+            }
+
+            self.settle_bundle_records(&bundle, BidStatus::Won);
+            let prices = self.bundle_prices(&bundle);
+
+            for (item_hash, price) in bundle.items.into_iter().zip(prices) {
+                winners.push((item_hash, Bid::new(&bundle.account_id, &price)));
+            }
+        }
+
+        winners
+    }
+
+    /// price of each lot that is sold to a single or a bundle bid if the auction is finished now
+    pub(crate) fn winning_prices(&self) -> Vec<(ItemHash, Money)> {
+        let bids: Vec<(ItemHash, Bid)> = self.items_and_bids.iter().collect();
+        let bundle_bids = self.bundle_bids.to_vec();
+        let (won_bids, won_bundles) = self.allocate_bids(&bids, &bundle_bids);
+
+        let mut prices: Vec<(ItemHash, Money)> = bids
+            .into_iter()
+            .zip(won_bids)
+            .filter(|(_, won)| *won)
+            .map(|((item_hash, bid), _)| (item_hash, bid.bid))
+            .collect();
+
+        for (bundle, won) in bundle_bids.iter().zip(won_bundles) {
+            if won {
+                let items = bundle.items.iter().cloned();
+                prices.extend(items.zip(self.bundle_prices(bundle)));
+            }
+        }
+
+        prices
+    }
+
+    /// unlock the bundle bid in the bidder's balance and mark it refunded in bid history
+    pub(crate) fn refund_bundle_bid(&mut self, bundle: &BundleBid) {
+        self.unlock(&bundle.account_id, bundle.amount);
+        self.settle_bundle_records(bundle, BidStatus::Refunded);
+    }
+
+    /// pick the combination of single and bundle bids with the biggest revenue
+    /// (see `allocation::allocate`). Bundles with delisted lots do not compete
+    ///
+    /// return whether each single bid and each bundle bid has been accepted
+    fn allocate_bids(
+        &self,
+        bids: &[(ItemHash, Bid)],
+        bundle_bids: &[BundleBid],
+    ) -> (Vec<bool>, Vec<bool>) {
+        let mut candidates: Vec<Candidate> = bids
+            .iter()
+            .map(|(item_hash, bid)| Candidate {
+                items: vec![item_hash.clone()],
+                amount: bid.bid,
+            })
+            .collect();
+
+        // bundles with delisted lots can not be bought as a whole so they do not compete
+        let mut bundle_indices = vec![];
+
+        for (index, bundle) in bundle_bids.iter().enumerate() {
+            if bundle
+                .items
+                .iter()
+                .all(|item| self.lots.get(item).is_some())
+            {
+                candidates.push(Candidate {
+                    items: bundle.items.clone(),
+                    amount: bundle.amount,
+                });
+                bundle_indices.push(index);
+            }
+        }
+
+        let accepted = allocation::allocate(&candidates);
+        let is_accepted = |candidate: usize| accepted.binary_search(&candidate).is_ok();
+
+        let won_bids = (0..bids.len()).map(is_accepted).collect();
+        let mut won_bundles = vec![false; bundle_bids.len()];
+
+        for (position, index) in bundle_indices.into_iter().enumerate() {
+            won_bundles[index] = is_accepted(bids.len() + position);
+        }
+
+        (won_bids, won_bundles)
+    }
+
+    /// split the price of the bundle between its lots (see `allocation::split_price`)
+    fn bundle_prices(&self, bundle: &BundleBid) -> Vec<Money> {
+        let min_bids: Vec<Money> = bundle
+            .items
+            .iter()
+            .map(|item_hash| self.get_lot_item(item_hash).unwrap().min_bid)
+            .collect();
+
+        allocation::split_price(bundle.amount, &min_bids)
+    }
+
+    fn settle_bundle_records(&mut self, bundle: &BundleBid, status: BidStatus) {
+        for (item_hash, index) in bundle.items.iter().zip(bundle.records.iter()) {
+            self.settle_bid(item_hash, *index, status);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::views::LotFilter;
+
+    fn setup_bundle_auction(
+        bids: [(&str, Option<usize>, Money); 3],
+    ) -> (TestAuction, Vec<ItemHash>) {
+        let mut exchange = setup_auction();
+
+        let items = vec![
+            exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None),
+            exchange.add_item_to_auction(&"another_test_item".to_string(), U128(10), None),
+        ];
+
+        // bid for a single item if the item index is set, for both items otherwise
+        for (bidder, item, amount) in bids {
+            call_contract(bidder, amount);
+
+            match item {
+                Some(item) => exchange.make_bid(&items[item], U128(amount)),
+                None => {
+                    exchange.make_bundle_bid(items.clone(), U128(amount));
+                }
+            }
+        }
+
+        (exchange, items)
+    }
+
+    #[test]
+    fn test_bundle_bid_wins() {
+        let (mut exchange, items) = setup_bundle_auction([
+            ("alice.near", Some(0), 10),
+            ("carol.near", Some(1), 10),
+            ("dave.near", None, 25),
+        ]);
+
+        assert_eq!(exchange.get_bundle_bids(None, None)[0].items, items);
+
+        call_contract("bob.near", 0);
+        exchange.produce_auction();
+
+        let dave = account("dave.near");
+        let purchases = exchange.get_purchases(dave.clone(), None, None);
+        let prices: Vec<U128> = purchases.iter().map(|purchase| purchase.price).collect();
+
+        assert_eq!(prices, vec![U128(12), U128(13)]);
+        assert_eq!(exchange.get_balance(dave).locked, U128(0));
+
+        for bidder in ["alice.near", "carol.near"] {
+            let bidder = account(bidder);
+
+            assert_eq!(exchange.get_balance(bidder.clone()).free, U128(10));
+            assert_eq!(
+                exchange.get_bids_by_account(bidder, None, None)[0].status,
+                BidStatus::Refunded
+            );
+        }
+
+        let round = exchange.get_round(U64(1)).unwrap();
+        assert!(round
+            .lots
+            .iter()
+            .all(|lot| lot.winner == Some(account("dave.near"))));
+    }
+
+    #[test]
+    fn test_bundle_bid_history() {
+        let (mut exchange, items) = setup_bundle_auction([
+            ("alice.near", Some(0), 10),
+            ("carol.near", Some(1), 10),
+            ("dave.near", None, 25),
+        ]);
+
+        let history = exchange.get_bid_history(items[0].clone(), None, None);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].status, BidStatus::Leading);
+        assert_eq!(history[1].kind, BidKind::Bundle);
+        assert_eq!(history[1].status, BidStatus::Pending);
+
+        let dave = account("dave.near");
+        let bids = exchange.get_bids_by_account(dave.clone(), None, None);
+        let lots: Vec<ItemHash> = bids.iter().map(|bid| bid.item_hash.clone()).collect();
+        assert_eq!(lots, items);
+        assert!(bids.iter().all(|bid| bid.amount == U128(25)));
+
+        // the bundle bid wins if the auction is finished now
+        let supplier = exchange.get_supplier(get_acc_id(), None, None, None, None);
+        assert_eq!(supplier.pending_proceeds, U128(25));
+
+        call_contract("bob.near", 0);
+        exchange.produce_auction();
+
+        assert!(exchange
+            .get_bids_by_account(dave, None, None)
+            .iter()
+            .all(|bid| bid.status == BidStatus::Won));
+    }
+
+    #[test]
+    fn test_lot_with_bundle_bid_has_bids() {
+        let mut exchange = setup_auction();
+        let items = vec![
+            exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None),
+            exchange.add_item_to_auction(&"another_test_item".to_string(), U128(10), None),
+        ];
+
+        call_contract("dave.near", 20);
+        exchange.make_bundle_bid(items, U128(20));
+
+        let filter = LotFilter {
+            has_bids: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(exchange.get_lots(None, None, Some(filter)).len(), 2);
+    }
+
+    #[test]
+    fn test_single_bids_beat_bundle_bid() {
+        let (mut exchange, _) = setup_bundle_auction([
+            ("alice.near", Some(0), 15),
+            ("carol.near", Some(1), 15),
+            ("dave.near", None, 25),
+        ]);
+
+        call_contract("bob.near", 0);
+        exchange.produce_auction();
+
+        let dave = account("dave.near");
+        assert!(exchange.get_purchases(dave.clone(), None, None).is_empty());
+        assert_eq!(exchange.get_balance(dave.clone()).free, U128(25));
+        assert!(exchange
+            .get_bids_by_account(dave, None, None)
+            .iter()
+            .all(|bid| bid.status == BidStatus::Refunded));
+
+        let alice = account("alice.near");
+        assert_eq!(exchange.get_purchases(alice.clone(), None, None).len(), 1);
+        assert_eq!(
+            exchange.get_bids_by_account(alice, None, None)[0].status,
+            BidStatus::Won
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Bundle bid must be at least the sum of min bids: 20. Actual: 15")]
+    fn test_bundle_bid_less_than_min_bids() {
+        setup_bundle_auction([
+            ("alice.near", Some(0), 10),
+            ("carol.near", Some(1), 10),
+            ("dave.near", None, 15),
+        ]);
+    }
+
+    #[test]
+    fn test_cancel_auction_with_bundle_bids() {
+        let (mut exchange, _) = setup_bundle_auction([
+            ("alice.near", Some(0), 10),
+            ("carol.near", Some(1), 10),
+            ("dave.near", None, 25),
+        ]);
+
+        call_contract("bob.near", 0);
+        // two lots with bids, the bundle bid, two lots to archive, their items and their supplier
+        assert!(!exchange.cancel_auction("fraud".to_string(), Some(U64(2))));
+        assert!(!exchange.cancel_auction("fraud".to_string(), Some(U64(2))));
+        assert!(!exchange.cancel_auction("fraud".to_string(), Some(U64(2))));
+        assert!(exchange.cancel_auction("fraud".to_string(), Some(U64(2))));
+
+        let dave = account("dave.near");
+        assert_eq!(exchange.get_balance(dave).free, U128(25));
+        assert_eq!(exchange.get_round(U64(1)).unwrap().refunds.len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "bundle_too_small: Bundle must contain at least 2 items")]
+    fn test_bundle_of_one_item() {
+        let (mut exchange, item_hash) = setup_auction_with_item(10);
+
+        call_contract("carol.near", 10);
+        exchange.make_bundle_bid(vec![item_hash], U128(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "multi_unit_bundle_item: Bundle can not contain multi-unit item")]
+    fn test_bundle_with_unit_lot() {
+        let (mut exchange, item_hash) = setup_auction_with_item(10);
+        let units_hash =
+            exchange.add_units_to_auction(&"ticket".to_string(), U64(3), U128(5), None);
+
+        call_contract("carol.near", 20);
+        exchange.make_bundle_bid(vec![item_hash, units_hash], U128(20));
+    }
+}
@@ -0,0 +1,246 @@
+use std::mem;
+
+use near_sdk::collections::Vector;
+use near_sdk::json_types::U64;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::errors::AuctionError;
+use crate::events::AuctionEvent;
+use crate::history::BidStatus;
+use crate::roles::Role;
+use crate::rounds::{LotOutcome, ProcurementOutcome, RoundStatus};
+use crate::supplier::Supplier;
+use crate::{Auction, AuctionExt, ItemHash, StorageKey, DEFAULT_BATCH_LIMIT};
+
+#[near_bindgen]
+impl Auction {
+    /// abort the current auction: unlock every bid in its bidder's balance and return every item
+    /// with its bond to its supplier. Large auctions are cancelled in several calls, each of them
+    /// processes up to `limit` lots with bids, multi-unit lots with bids, procurement requests,
+    /// bundle bids, lots to archive, suppliers and allowlisted accounts. Nobody can start a new
+    /// auction until the cancellation is finished
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - why the auction is cancelled. Only the reason from the first call is saved
+    /// * `limit` - max amount of entries to process in this call. `DEFAULT_BATCH_LIMIT` if not set
+    ///
+    /// return true if the cancellation is finished
+    ///
+    /// # Panics
+    ///
+    /// * caller must be an admin
+    /// * auction must be opened or being cancelled
+    pub fn cancel_auction(&mut self, reason: String, limit: Option<U64>) -> bool {
+        self.assert_role(&[Role::Admin]);
+
+        if self.cancellation_reason.is_none() {
+            if !self.auction_is_open {
+                AuctionError::AuctionFinished.panic()
+            }
+
+            self.auction_is_open = false;
+            self.cancellation_reason = Some(reason);
+
+            // the round archive takes these collections over when the cancellation is finished
+            self.cancellation_lots = Vector::new(StorageKey::RoundLots { round: self.round });
+            self.cancellation_procurements =
+                Vector::new(StorageKey::RoundProcurements { round: self.round });
+            self.cancellation_refunds = Vector::new(StorageKey::RoundRefunds { round: self.round });
+        }
+
+        let limit = limit.map_or(DEFAULT_BATCH_LIMIT, |limit| limit.0) as usize;
+
+        // each step runs only when the previous ones are done, otherwise the limit is exhausted
+        let mut processed = self.refund_lot_bids(limit);
+        processed += self.refund_unit_bids(limit - processed);
+        processed += self.refund_procurements(limit - processed);
+        processed += self.refund_bundle_bids(limit - processed);
+        processed += self.archive_cancelled_lots(limit - processed);
+        processed += self.remove_suppliers(limit - processed);
+        self.shrink_allowlist(limit - processed);
+
+        if !self.items_and_bids.is_empty()
+            || !self.unit_bids.is_empty()
+            || !self.procurements.is_empty()
+            || !self.bundle_bids.is_empty()
+            || !self.lots.is_empty()
+            || !self.suppliers.is_empty()
+            || !self.allowlist.is_empty()
+        {
+            return false;
+        }
+
+        let reason = self.cancellation_reason.take().unwrap();
+
+        // archived collections are handed over to the round as they are, the fields get back the
+        // empty collections that they have between cancellations
+        let lots = mem::replace(
+            &mut self.cancellation_lots,
+            Vector::new(b"cancellation_lots".to_vec()),
+        );
+        let procurements = mem::replace(
+            &mut self.cancellation_procurements,
+            Vector::new(b"cancellation_procurements".to_vec()),
+        );
+        let refunds = mem::replace(
+            &mut self.cancellation_refunds,
+            Vector::new(b"cancellation_refunds".to_vec()),
+        );
+
+        self.archive_round(
+            lots,
+            procurements,
+            refunds,
+            self.forfeited_bonds,
+            RoundStatus::Cancelled {
+                reason: reason.clone(),
+            },
+        );
+        self.clear_data();
+
+        AuctionEvent::AuctionCancelled {
+            round: self.round,
+            reason,
+        }
+        .emit();
+
+        true
+    }
+
+    /// check if the current auction is being cancelled
+    pub fn is_being_cancelled(&self) -> bool {
+        self.cancellation_reason.is_some()
+    }
+}
+
+impl Auction {
+    /// unlock bids for up to `limit` lots
+    ///
+    /// return amount of processed lots
+    fn refund_lot_bids(&mut self, limit: usize) -> usize {
+        let lots: Vec<ItemHash> = self.items_and_bids.keys().take(limit).collect();
+
+        for item_hash in lots.iter() {
+            for refund in self.release_lot_bids(item_hash) {
+                self.cancellation_refunds.push(&refund);
+            }
+
+            self.settle_bid_history(item_hash, BidStatus::Refunded);
+        }
+
+        lots.len()
+    }
+
+    /// unlock unit bids for up to `limit` multi-unit lots
+    ///
+    /// return amount of processed lots
+    fn refund_unit_bids(&mut self, limit: usize) -> usize {
+        let lots: Vec<ItemHash> = self.unit_bids.keys().take(limit).collect();
+
+        for item_hash in lots.iter() {
+            for refund in self.unlock_unit_bids(item_hash) {
+                self.cancellation_refunds.push(&refund);
+            }
+        }
+
+        lots.len()
+    }
+
+    /// unlock budgets of up to `limit` procurement requests
+    ///
+    /// return amount of processed requests
+    fn refund_procurements(&mut self, limit: usize) -> usize {
+        let requests: Vec<ItemHash> = self.procurements.keys().take(limit).collect();
+
+        for request_id in requests.iter() {
+            let (buyer, budget) = self.unlock_procurement(request_id);
+            self.cancellation_refunds.push(&(buyer.clone(), budget));
+            self.cancellation_procurements.push(&ProcurementOutcome {
+                request_id: request_id.clone(),
+                buyer,
+                seller: None,
+                price: 0,
+            });
+        }
+
+        requests.len()
+    }
+
+    /// unlock up to `limit` bundle bids
+    ///
+    /// return amount of processed bundle bids
+    fn refund_bundle_bids(&mut self, limit: usize) -> usize {
+        let mut processed = 0;
+
+        while processed < limit {
+            let bundle = match self.bundle_bids.pop() {
+                Some(bundle) => bundle,
+                None => break,
+            };
+
+            self.refund_bundle_bid(&bundle);
+            self.cancellation_refunds
+                .push(&(bundle.account_id, bundle.amount));
+            processed += 1;
+        }
+
+        processed
+    }
+
+    /// return bonds of up to `limit` lots to their suppliers and archive the lots as unsold
+    ///
+    /// return amount of processed lots
+    fn archive_cancelled_lots(&mut self, limit: usize) -> usize {
+        let lots: Vec<(ItemHash, AccountId)> = self.lots.iter().take(limit).collect();
+
+        for (item_hash, supplier_id) in lots.iter() {
+            let bond = self.get_lot_item(item_hash).map_or(0, |item| item.bond);
+            self.return_bond(supplier_id, bond);
+
+            self.cancellation_lots.push(&LotOutcome::new(
+                item_hash.clone(),
+                supplier_id.clone(),
+                None,
+            ));
+            self.lots.remove(item_hash);
+        }
+
+        lots.len()
+    }
+
+    /// remove items and suppliers of the cancelled auction, up to `limit` of them together. Lots
+    /// of the suppliers have to be archived
+    ///
+    /// return amount of removed items and suppliers
+    fn remove_suppliers(&mut self, limit: usize) -> usize {
+        let suppliers: Vec<(AccountId, Supplier)> = self.suppliers.iter().take(limit).collect();
+        let mut processed = 0;
+
+        for (supplier_id, mut supplier) in suppliers {
+            if processed == limit {
+                break;
+            }
+
+            processed += supplier.clear_items(limit - processed);
+
+            if supplier.items.is_empty() && processed < limit {
+                self.suppliers.remove(&supplier_id);
+                processed += 1;
+            } else {
+                self.suppliers.insert(&supplier_id, &supplier);
+            }
+        }
+
+        processed
+    }
+
+    /// remove up to `limit` accounts from the allowlist of the cancelled auction
+    fn shrink_allowlist(&mut self, limit: usize) {
+        let accounts: Vec<AccountId> = self.allowlist.iter().take(limit).collect();
+
+        for account_id in accounts.iter() {
+            self.allowlist.remove(account_id);
+        }
+    }
+}
@@ -0,0 +1,498 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base64VecU8, U64};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::errors::AuctionError;
+use crate::events::AuctionEvent;
+use crate::history::{BidKind, BidStatus};
+use crate::roles::Role;
+use crate::views::CandleEndingView;
+use crate::{Auction, AuctionExt, Bid, ItemHash, Money};
+
+/// Public ending period of a candle auction. The auction is closed at a random moment of the
+/// period that is chosen only after the period is over, so bidders can not wait for the last
+/// second to snipe lots
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq)]
+pub struct CandleEnding {
+    pub starts_at: u64, // nanoseconds
+    pub ends_at: u64,   // nanoseconds. Bids are not accepted after this moment
+}
+
+impl CandleEnding {
+    /// pick the close time from the ending period. Anybody can check it with the random seed
+    /// of the block that has produced the auction. The seed is known to the block producer in
+    /// advance (see `produce_auction`)
+    ///
+    /// # Arguments
+    ///
+    /// * `random_seed` - at least 8 random bytes
+    pub fn close_time(&self, random_seed: &[u8]) -> u64 {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&random_seed[..8]);
+
+        self.starts_at + u64::from_le_bytes(bytes) % (self.ends_at - self.starts_at)
+    }
+}
+
+#[near_bindgen]
+impl Auction {
+    /// turn the current auction into a candle auction. Only bids made not later than a random
+    /// close time inside the ending period count. Outbid money stays locked until the auction is
+    /// produced because an outbid bid can still win
+    ///
+    /// # Arguments
+    ///
+    /// * `starts_at` - beginning of the ending period in nanoseconds
+    /// * `ends_at` - end of the ending period in nanoseconds
+    ///
+    /// # Panics
+    ///
+    /// * caller must be an admin
+    /// * auction must be opened and must not have bids for single lots yet
+    /// * ending period must be in the future and must not be empty
+    pub fn set_candle_ending(&mut self, starts_at: U64, ends_at: U64) {
+        self.assert_role(&[Role::Admin]);
+        if !self.auction_is_open {
+            AuctionError::AuctionClosed.panic()
+        }
+        if !self.items_and_bids.is_empty() {
+            AuctionError::CandleEndingAfterBids.panic()
+        }
+        if starts_at.0 >= ends_at.0 || ends_at.0 <= env::block_timestamp() {
+            AuctionError::InvalidCandleEnding.panic()
+        }
+
+        self.candle_ending = Some(CandleEnding {
+            starts_at: starts_at.0,
+            ends_at: ends_at.0,
+        });
+    }
+
+    /// return ending period of the current candle auction. None if the auction is not a candle one
+    pub fn get_candle_ending(&self) -> Option<CandleEndingView> {
+        self.candle_ending.clone().map(CandleEndingView::from)
+    }
+}
+
+impl Auction {
+    /// money that the bidder has already locked for the lot
+    ///
+    /// # Arguments
+    ///
+    /// * `bidder` - account that makes a bid
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    pub(crate) fn locked_for_lot(&self, bidder: &AccountId, item_hash: &ItemHash) -> Money {
+        if self.candle_ending.is_some() {
+            // bids only grow so the last bid of the bidder is the biggest one
+            return self
+                .bid_history
+                .get(item_hash)
+                .and_then(|history| {
+                    history.iter().rev().find(|record| {
+                        record.account_id == *bidder && record.kind == BidKind::Single
+                    })
+                })
+                .map_or(0, |record| record.amount);
+        }
+
+        match self.items_and_bids.get(item_hash) {
+            Some(bid) if bid.account_id == *bidder => bid.bid,
+            _ => 0,
+        }
+    }
+
+    /// remove bids for the lot and unlock their money
+    ///
+    /// return bidder and unlocked amount of each bid
+    pub(crate) fn release_lot_bids(&mut self, item_hash: &ItemHash) -> Vec<(AccountId, Money)> {
+        let leading_bid = self.items_and_bids.remove(item_hash);
+
+        let locked = if self.candle_ending.is_some() {
+            self.locked_by_bidders(item_hash)
+        } else {
+            leading_bid
+                .map(|bid| vec![(bid.account_id, bid.bid)])
+                .unwrap_or_default()
+        };
+
+        for (account_id, amount) in locked.iter() {
+            self.unlock(account_id, *amount);
+        }
+
+        locked
+    }
+
+    /// pick the close time of the candle auction and make the last bid before it the leading bid
+    /// of each lot and the last ask before it the best ask of each procurement request. Bundle and
+    /// unit bids made after the close time are dropped. Money of other bids is unlocked. Does
+    /// nothing if the auction is not a candle one
+    ///
+    /// # Panics
+    ///
+    /// * ending period is not over
+    pub(crate) fn close_candle(&mut self) {
+        let ending = match self.candle_ending.clone() {
+            Some(ending) => ending,
+            None => return,
+        };
+
+        if env::block_timestamp() < ending.ends_at {
+            AuctionError::CandleNotOver {
+                ends_at: U64(ending.ends_at),
+            }
+            .panic()
+        }
+
+        let random_seed = env::random_seed();
+        let close_time = ending.close_time(&random_seed);
+
+        let lots: Vec<ItemHash> = self.items_and_bids.keys().collect();
+
+        for item_hash in lots.iter() {
+            self.close_lot_at(item_hash, close_time);
+        }
+
+        self.drop_bundle_bids_after(close_time);
+        self.drop_unit_bids_after(close_time);
+        self.close_procurements_at(close_time);
+
+        AuctionEvent::CandleClosed {
+            round: self.round,
+            close_time: U64(close_time),
+            random_seed: Base64VecU8(random_seed),
+        }
+        .emit();
+    }
+
+    fn close_lot_at(&mut self, item_hash: &ItemHash, close_time: u64) {
+        let locked = self.locked_by_bidders(item_hash);
+
+        let mut history = self.bid_history.get(item_hash).unwrap();
+        let records = history.to_vec();
+        let winner = records
+            .iter()
+            .rposition(|record| record.kind == BidKind::Single && record.timestamp <= close_time);
+
+        for (account_id, amount) in locked {
+            // the winner keeps his winning bid locked
+            let kept = match winner {
+                Some(index) if records[index].account_id == account_id => records[index].amount,
+                _ => 0,
+            };
+
+            if amount > kept {
+                self.unlock(&account_id, amount - kept);
+            }
+        }
+
+        for (index, mut record) in records.iter().cloned().enumerate() {
+            // bundle bids are settled separately
+            if record.kind != BidKind::Single {
+                continue;
+            }
+
+            let status = if Some(index) == winner {
+                BidStatus::Leading
+            } else {
+                BidStatus::Outbid
+            };
+
+            if record.status != status {
+                record.status = status;
+                history.replace(index as u64, &record);
+            }
+        }
+
+        match winner {
+            Some(index) => {
+                let record = &records[index];
+                self.items_and_bids
+                    .insert(item_hash, &Bid::new(&record.account_id, &record.amount));
+            }
+            None => {
+                self.items_and_bids.remove(item_hash);
+                self.settle_bid_history(item_hash, BidStatus::Refunded);
+            }
+        }
+    }
+
+    /// return each bidder of the lot with money locked for it
+    fn locked_by_bidders(&self, item_hash: &ItemHash) -> Vec<(AccountId, Money)> {
+        let mut locked: Vec<(AccountId, Money)> = vec![];
+
+        if let Some(history) = self.bid_history.get(item_hash) {
+            for record in history
+                .iter()
+                .filter(|record| record.kind == BidKind::Single)
+            {
+                match locked
+                    .iter_mut()
+                    .find(|(account_id, _)| *account_id == record.account_id)
+                {
+                    Some((_, amount)) => *amount = record.amount,
+                    None => locked.push((record.account_id, record.amount)),
+                }
+            }
+        }
+
+        locked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[test]
+    fn test_close_time_is_inside_ending() {
+        let ending = CandleEnding {
+            starts_at: 100,
+            ends_at: 200,
+        };
+
+        assert_eq!(ending.close_time(&[0; 32]), 100);
+        assert_eq!(
+            ending.close_time(&[250; 32]),
+            100 + u64::from_le_bytes([250; 8]) % 100
+        );
+
+        for byte in 0..=255 {
+            let close_time = ending.close_time(&[byte; 32]);
+            assert!((100..200).contains(&close_time));
+        }
+    }
+
+    fn setup_candle_auction() -> (TestAuction, ItemHash) {
+        let (mut exchange, item_hash) = setup_auction_with_item(5);
+        exchange.set_candle_ending(U64(100), U64(200));
+
+        // alice bids before the ending period, carol outbids her inside it and alice raises her bid
+        for (bidder, amount, deposit, timestamp) in [
+            ("alice.near", 10, 10, 50),
+            ("carol.near", 20, 20, 150),
+            ("alice.near", 30, 20, 190),
+        ] {
+            call_at(bidder, deposit, timestamp);
+            exchange.make_bid(&item_hash, U128(amount));
+        }
+
+        (exchange, item_hash)
+    }
+
+    fn produce_candle_auction(exchange: &mut Auction, random_seed: [u8; 32]) {
+        testing_env!(VMContextBuilder::new()
+            .block_timestamp(200)
+            .random_seed(random_seed)
+            .build());
+        exchange.produce_auction();
+    }
+
+    #[test]
+    fn test_candle_auction_outbid_money_stays_locked() {
+        let (exchange, _) = setup_candle_auction();
+
+        let carol = account("carol.near");
+        assert_eq!(exchange.get_balance(carol).locked, U128(20));
+
+        let alice = account("alice.near");
+        assert_eq!(exchange.get_balance(alice).locked, U128(30));
+    }
+
+    #[test]
+    fn test_candle_auction_closes_before_last_bids() {
+        let (mut exchange, _) = setup_candle_auction();
+
+        // the close time is 100 + 60 = 160 so the last bid of alice does not count
+        let mut random_seed = [0; 32];
+        random_seed[0] = 60;
+        produce_candle_auction(&mut exchange, random_seed);
+
+        let carol = account("carol.near");
+        let purchases = exchange.get_purchases(carol.clone(), None, None);
+        assert_eq!(purchases[0].price, U128(20));
+        assert_eq!(exchange.get_balance(carol.clone()).locked, U128(0));
+        assert_eq!(
+            exchange.get_bids_by_account(carol, None, None)[0].status,
+            BidStatus::Won
+        );
+
+        let alice = account("alice.near");
+        assert!(exchange.get_purchases(alice.clone(), None, None).is_empty());
+        assert_eq!(exchange.get_balance(alice).free, U128(30));
+
+        let logs = get_logs();
+        assert!(logs
+            .iter()
+            .any(|log| log.contains(r#""event":"candle_closed""#)
+                && log.contains(r#""close_time":"160""#)));
+    }
+
+    #[test]
+    fn test_candle_auction_winner_pays_bid_before_close() {
+        let (mut exchange, _) = setup_candle_auction();
+
+        // the close time is the beginning of the ending period, only the first bid counts
+        produce_candle_auction(&mut exchange, [0; 32]);
+
+        let alice = account("alice.near");
+        let purchases = exchange.get_purchases(alice.clone(), None, None);
+        assert_eq!(purchases[0].price, U128(10));
+        assert_eq!(exchange.get_balance(alice.clone()).free, U128(20));
+        assert_eq!(exchange.get_balance(alice).locked, U128(0));
+
+        let carol = account("carol.near");
+        assert_eq!(exchange.get_balance(carol).free, U128(20));
+    }
+
+    #[test]
+    #[should_panic(expected = "Candle auction has ended")]
+    fn test_bid_after_candle_ending() {
+        let (mut exchange, item_hash) = setup_candle_auction();
+
+        call_at("carol.near", 20, 201);
+        exchange.make_bid(&item_hash, U128(40));
+    }
+
+    #[test]
+    #[should_panic(expected = "Candle ending is not over. It ends at 200")]
+    fn test_produce_candle_auction_too_early() {
+        let (mut exchange, _) = setup_candle_auction();
+
+        call_at("bob.near", 0, 199);
+        exchange.produce_auction();
+    }
+
+    #[test]
+    fn test_cancel_candle_auction_unlocks_outbid_bids() {
+        let (mut exchange, _) = setup_candle_auction();
+
+        call_contract("bob.near", 0);
+        assert!(exchange.cancel_auction("fraud".to_string(), None));
+
+        for (bidder, free) in [("alice.near", 30), ("carol.near", 20)] {
+            let bidder = account(bidder);
+            assert_eq!(exchange.get_balance(bidder.clone()).free, U128(free));
+            assert_eq!(exchange.get_balance(bidder).locked, U128(0));
+        }
+    }
+
+    fn close_candle_at_160(exchange: &mut Auction) {
+        let mut random_seed = [0; 32];
+        random_seed[0] = 60;
+        produce_candle_auction(exchange, random_seed);
+    }
+
+    #[test]
+    fn test_candle_auction_drops_late_bundle_bids() {
+        let mut exchange = setup_auction();
+        let items = vec![
+            exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None),
+            exchange.add_item_to_auction(&"another_test_item".to_string(), U128(10), None),
+        ];
+        exchange.set_candle_ending(U64(100), U64(200));
+
+        call_at("alice.near", 10, 150);
+        exchange.make_bid(&items[0], U128(10));
+        call_at("carol.near", 10, 150);
+        exchange.make_bid(&items[1], U128(10));
+        call_at("dave.near", 25, 190);
+        exchange.make_bundle_bid(items, U128(25));
+
+        close_candle_at_160(&mut exchange);
+
+        let dave = account("dave.near");
+        assert!(exchange.get_purchases(dave.clone(), None, None).is_empty());
+        assert_eq!(exchange.get_balance(dave.clone()).free, U128(25));
+        assert_eq!(exchange.get_balance(dave).locked, U128(0));
+
+        for bidder in ["alice.near", "carol.near"] {
+            let bidder = account(bidder);
+            assert_eq!(exchange.get_purchases(bidder, None, None).len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_candle_auction_drops_late_unit_bids() {
+        let mut exchange = setup_auction();
+        let item_hash = exchange.add_units_to_auction(&"ticket".to_string(), U64(3), U128(5), None);
+        exchange.set_candle_ending(U64(100), U64(200));
+
+        call_at("alice.near", 20, 150);
+        exchange.make_unit_bid(item_hash.clone(), U64(2), U128(10));
+        call_at("carol.near", 16, 190);
+        exchange.make_unit_bid(item_hash, U64(2), U128(8));
+
+        close_candle_at_160(&mut exchange);
+
+        let alice = account("alice.near");
+        let purchases = exchange.get_purchases(alice, None, None);
+        assert_eq!(purchases[0].quantity, U64(2));
+        assert_eq!(purchases[0].price, U128(20));
+
+        let carol = account("carol.near");
+        assert!(exchange.get_purchases(carol.clone(), None, None).is_empty());
+        assert_eq!(exchange.get_balance(carol.clone()).free, U128(16));
+        assert_eq!(exchange.get_balance(carol).locked, U128(0));
+    }
+
+    #[test]
+    fn test_candle_auction_ignores_late_asks() {
+        let mut exchange = setup_auction();
+        exchange.set_candle_ending(U64(100), U64(200));
+
+        call_at("alice.near", 100, 50);
+        let request_id = exchange.create_procurement("100 chairs".to_string(), U128(100));
+        call_at("carol.near", STORAGE_DEPOSIT, 150);
+        exchange.make_ask(request_id.clone(), U128(80));
+        call_at("dave.near", STORAGE_DEPOSIT, 190);
+        exchange.make_ask(request_id, U128(60));
+
+        close_candle_at_160(&mut exchange);
+
+        let alice = account("alice.near");
+        let purchases = exchange.get_purchases(alice.clone(), None, None);
+        assert_eq!(purchases[0].price, U128(80));
+        assert_eq!(purchases[0].supplier, account("carol.near"));
+        assert_eq!(exchange.get_balance(alice).free, U128(20));
+
+        let dave = account("dave.near");
+        assert_eq!(
+            exchange.get_supplier(dave, None, None, None, None).paid_proceeds,
+            U128(0)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "candle_ended: Candle auction has ended")]
+    fn test_ask_after_candle_ending() {
+        let mut exchange = setup_auction();
+        exchange.set_candle_ending(U64(100), U64(200));
+
+        call_at("alice.near", 100, 50);
+        let request_id = exchange.create_procurement("100 chairs".to_string(), U128(100));
+        call_at("carol.near", 0, 201);
+        exchange.make_ask(request_id, U128(80));
+    }
+
+    #[test]
+    #[should_panic(expected = "candle_ending_after_bids: Candle ending can not be set")]
+    fn test_candle_ending_after_bid() {
+        let (mut exchange, item_hash) = setup_auction_with_item(10);
+        call_contract("alice.near", 10);
+        exchange.make_bid(&item_hash, U128(10));
+
+        call_contract("bob.near", 0);
+        exchange.set_candle_ending(U64(100), U64(200));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid_candle_ending: Candle ending must be a future period")]
+    fn test_reversed_candle_ending() {
+        let mut exchange = setup_auction();
+        exchange.set_candle_ending(U64(200), U64(100));
+    }
+}
@@ -0,0 +1,487 @@
+use std::fmt;
+
+#[cfg(target_arch = "wasm32")]
+use near_sdk::env;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::AccountId;
+
+use crate::access::INVITE_CODE_HASH_LENGTH;
+use crate::metadata::MEDIA_HASH_LENGTH;
+use crate::procurement::MAX_DESCRIPTION_LENGTH;
+use crate::roles::Role;
+use crate::supplier::ItemHash;
+
+/// Reason why a call of the contract fails. Codes are stable, so clients can rely on them
+/// instead of messages. A failed call panics with `<code>: <message>` (see `AuctionError::panic`)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum AuctionError {
+    // access
+    MissingRole {
+        account_id: AccountId,
+        roles: Vec<Role>,
+    },
+    InvalidInviteCodeHash,
+    InvalidInviteCode,
+    InviteCodesNotUsed,
+    AccountNotAllowed {
+        account_id: AccountId,
+    },
+    AccountBanned {
+        account_id: AccountId,
+    },
+    ContractPaused,
+    AlreadyPaused,
+    NotPaused,
+
+    // auction lifecycle
+    AuctionClosed,
+    AuctionAlreadyOpened,
+    AuctionFinished,
+    CancellationInProgress,
+
+    // listing
+    NotSupplier {
+        account_id: AccountId,
+    },
+    ItemAlreadyAdded {
+        item: String,
+    },
+    ItemHasBids {
+        item_hash: ItemHash,
+    },
+    InvalidListingBond {
+        bond: U128,
+        attached: U128,
+    },
+    InvalidQuantity,
+    TooFewUnits {
+        quantity: U64,
+    },
+    EmptyTitle,
+    UnpairedMedia,
+    InvalidMediaHash,
+    InvalidExtra,
+    FieldTooLong {
+        field: String,
+        max_length: U64,
+        length: U64,
+    },
+
+    // bids
+    OwnItem,
+    ItemNotFound {
+        item_hash: ItemHash,
+    },
+    MultiUnitItem {
+        item_hash: ItemHash,
+        quantity: U64,
+    },
+    SingleUnitItem {
+        item_hash: ItemHash,
+    },
+    BelowMinBid {
+        min_bid: U128,
+        amount: U128,
+    },
+    BiggerBidExists {
+        current_bid: U128,
+    },
+    EmptyBids,
+    DuplicateItem {
+        item_hash: ItemHash,
+    },
+    UnitsOutOfRange {
+        max: U64,
+        quantity: U64,
+    },
+    BidTooBig,
+    BundleTooSmall,
+    MultiUnitBundleItem {
+        item_hash: ItemHash,
+    },
+    BundleBelowMinBids {
+        min_bids: U128,
+        amount: U128,
+    },
+    CandleEnded,
+    CandleEndingAfterBids,
+    InvalidCandleEnding,
+    CandleNotOver {
+        ends_at: U64,
+    },
+
+    // escrow
+    NoDeposit,
+    NotEnoughBalance {
+        free: U128,
+        required: U128,
+    },
+    NotEnoughBalanceForBids {
+        free: U128,
+        required: U128,
+    },
+    StorageNotCovered {
+        required: U128,
+        attached: U128,
+    },
+
+    // procurement
+    InvalidDescription,
+    ZeroBudget,
+    ProcurementNotFound {
+        request_id: ItemHash,
+    },
+    OwnRequest,
+    ZeroAsk,
+    AskAboveBudget {
+        budget: U128,
+        amount: U128,
+    },
+    SmallerAskExists {
+        current_ask: U128,
+    },
+
+    // signed bids
+    InvalidPublicKey,
+    BidKeyNotFound {
+        account_id: AccountId,
+    },
+    SignedBidExpired,
+    InvalidNonce {
+        last_nonce: U64,
+        nonce: U64,
+    },
+    InvalidSignature,
+
+    // solvency
+    Insolvent {
+        liabilities: U128,
+        balance: U128,
+    },
+}
+
+impl AuctionError {
+    /// stable code of the error. The same as the `code` field of its JSON
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::MissingRole { .. } => "missing_role",
+            Self::InvalidInviteCodeHash => "invalid_invite_code_hash",
+            Self::InvalidInviteCode => "invalid_invite_code",
+            Self::InviteCodesNotUsed => "invite_codes_not_used",
+            Self::AccountNotAllowed { .. } => "account_not_allowed",
+            Self::AccountBanned { .. } => "account_banned",
+            Self::ContractPaused => "contract_paused",
+            Self::AlreadyPaused => "already_paused",
+            Self::NotPaused => "not_paused",
+            Self::AuctionClosed => "auction_closed",
+            Self::AuctionAlreadyOpened => "auction_already_opened",
+            Self::AuctionFinished => "auction_finished",
+            Self::CancellationInProgress => "cancellation_in_progress",
+            Self::NotSupplier { .. } => "not_supplier",
+            Self::ItemAlreadyAdded { .. } => "item_already_added",
+            Self::ItemHasBids { .. } => "item_has_bids",
+            Self::InvalidListingBond { .. } => "invalid_listing_bond",
+            Self::InvalidQuantity => "invalid_quantity",
+            Self::TooFewUnits { .. } => "too_few_units",
+            Self::EmptyTitle => "empty_title",
+            Self::UnpairedMedia => "unpaired_media",
+            Self::InvalidMediaHash => "invalid_media_hash",
+            Self::InvalidExtra => "invalid_extra",
+            Self::FieldTooLong { .. } => "field_too_long",
+            Self::OwnItem => "own_item",
+            Self::ItemNotFound { .. } => "item_not_found",
+            Self::MultiUnitItem { .. } => "multi_unit_item",
+            Self::SingleUnitItem { .. } => "single_unit_item",
+            Self::BelowMinBid { .. } => "below_min_bid",
+            Self::BiggerBidExists { .. } => "bigger_bid_exists",
+            Self::EmptyBids => "empty_bids",
+            Self::DuplicateItem { .. } => "duplicate_item",
+            Self::UnitsOutOfRange { .. } => "units_out_of_range",
+            Self::BidTooBig => "bid_too_big",
+            Self::BundleTooSmall => "bundle_too_small",
+            Self::MultiUnitBundleItem { .. } => "multi_unit_bundle_item",
+            Self::BundleBelowMinBids { .. } => "bundle_below_min_bids",
+            Self::CandleEnded => "candle_ended",
+            Self::CandleEndingAfterBids => "candle_ending_after_bids",
+            Self::InvalidCandleEnding => "invalid_candle_ending",
+            Self::CandleNotOver { .. } => "candle_not_over",
+            Self::NoDeposit => "no_deposit",
+            Self::NotEnoughBalance { .. } => "not_enough_balance",
+            Self::NotEnoughBalanceForBids { .. } => "not_enough_balance_for_bids",
+            Self::StorageNotCovered { .. } => "storage_not_covered",
+            Self::InvalidDescription => "invalid_description",
+            Self::ZeroBudget => "zero_budget",
+            Self::ProcurementNotFound { .. } => "procurement_not_found",
+            Self::OwnRequest => "own_request",
+            Self::ZeroAsk => "zero_ask",
+            Self::AskAboveBudget { .. } => "ask_above_budget",
+            Self::SmallerAskExists { .. } => "smaller_ask_exists",
+            Self::InvalidPublicKey => "invalid_public_key",
+            Self::BidKeyNotFound { .. } => "bid_key_not_found",
+            Self::SignedBidExpired => "signed_bid_expired",
+            Self::InvalidNonce { .. } => "invalid_nonce",
+            Self::InvalidSignature => "invalid_signature",
+            Self::Insolvent { .. } => "insolvent",
+        }
+    }
+
+    /// abort the call with `<code>: <message>`
+    pub fn panic(&self) -> ! {
+        let message = format!("{}: {}", self.code(), self);
+
+        // mocked blockchain of unit tests can not unwind from `env::panic_str`
+        #[cfg(not(target_arch = "wasm32"))]
+        panic!("{}", message);
+
+        #[cfg(target_arch = "wasm32")]
+        env::panic_str(&message)
+    }
+}
+
+impl fmt::Display for AuctionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingRole { account_id, roles } => write!(
+                f,
+                "{} does not have any of the required roles: {:?}",
+                account_id, roles
+            ),
+            Self::InvalidInviteCodeHash => write!(
+                f,
+                "Invite code hash must be {} bytes long",
+                INVITE_CODE_HASH_LENGTH
+            ),
+            Self::InvalidInviteCode => write!(f, "Invalid invite code"),
+            Self::InviteCodesNotUsed => write!(f, "Auction does not use invite codes"),
+            Self::AccountNotAllowed { account_id } => write!(
+                f,
+                "Account {} is not allowed to take part in this auction",
+                account_id
+            ),
+            Self::AccountBanned { account_id } => write!(f, "Account {} is banned", account_id),
+            Self::ContractPaused => write!(f, "Contract is paused"),
+            Self::AlreadyPaused => write!(f, "Contract is already paused"),
+            Self::NotPaused => write!(f, "Contract is not paused"),
+            Self::AuctionClosed => write!(f, "Auction is closed. Try again later"),
+            Self::AuctionAlreadyOpened => write!(f, "Auction is already opened"),
+            Self::AuctionFinished => write!(f, "Auction has already been finished"),
+            Self::CancellationInProgress => write!(f, "Previous auction is being cancelled"),
+            Self::NotSupplier { account_id } => write!(f, "{} is not a supplier", account_id),
+            Self::ItemAlreadyAdded { item } => {
+                write!(f, "Item {} has already been added to the auction", item)
+            }
+            Self::ItemHasBids { item_hash } => {
+                write!(f, "Item with hash {} already has bids", item_hash)
+            }
+            Self::InvalidListingBond { bond, attached } => {
+                write!(f, "Listing bond is {}. Attached: {}", bond.0, attached.0)
+            }
+            Self::InvalidQuantity => write!(f, "Quantity must be positive"),
+            Self::TooFewUnits { quantity } => write!(
+                f,
+                "Multi-unit lot must contain at least 2 units. Actual: {}",
+                quantity.0
+            ),
+            Self::EmptyTitle => write!(f, "Item title can not be empty"),
+            Self::UnpairedMedia => write!(f, "Media and media hash must be provided together"),
+            Self::InvalidMediaHash => {
+                write!(f, "Media hash must be {} bytes long", MEDIA_HASH_LENGTH)
+            }
+            Self::InvalidExtra => write!(f, "Item extra must be a valid JSON"),
+            Self::FieldTooLong {
+                field,
+                max_length,
+                length,
+            } => write!(
+                f,
+                "Item {} is too long. Max length: {}, actual: {}",
+                field, max_length.0, length.0
+            ),
+            Self::OwnItem => write!(f, "Supplier can not make bid for his items"),
+            Self::ItemNotFound { item_hash } => {
+                write!(f, "Item with hash {} does not exist", item_hash)
+            }
+            Self::MultiUnitItem {
+                item_hash,
+                quantity,
+            } => write!(
+                f,
+                "Item with hash {} has {} units. Use make_unit_bid",
+                item_hash, quantity.0
+            ),
+            Self::SingleUnitItem { item_hash } => write!(
+                f,
+                "Item with hash {} is a single unit. Use make_bid",
+                item_hash
+            ),
+            Self::BelowMinBid { min_bid, amount } => write!(
+                f,
+                "This item has {} minimum bid. Actual: {}",
+                min_bid.0, amount.0
+            ),
+            Self::BiggerBidExists { .. } => write!(f, "A bigger bid for this item already exists"),
+            Self::EmptyBids => write!(f, "Bids can not be empty"),
+            Self::DuplicateItem { item_hash } => {
+                write!(f, "Item with hash {} is bid twice", item_hash)
+            }
+            Self::UnitsOutOfRange { max, quantity } => write!(
+                f,
+                "Quantity must be from 1 to {}. Actual: {}",
+                max.0, quantity.0
+            ),
+            Self::BidTooBig => write!(f, "Bid is too big"),
+            Self::BundleTooSmall => write!(f, "Bundle must contain at least 2 items"),
+            Self::MultiUnitBundleItem { item_hash } => {
+                write!(f, "Bundle can not contain multi-unit item {}", item_hash)
+            }
+            Self::BundleBelowMinBids { min_bids, amount } => write!(
+                f,
+                "Bundle bid must be at least the sum of min bids: {}. Actual: {}",
+                min_bids.0, amount.0
+            ),
+            Self::CandleEnded => write!(f, "Candle auction has ended"),
+            Self::CandleEndingAfterBids => {
+                write!(f, "Candle ending can not be set after the first bid")
+            }
+            Self::InvalidCandleEnding => write!(f, "Candle ending must be a future period"),
+            Self::CandleNotOver { ends_at } => {
+                write!(f, "Candle ending is not over. It ends at {}", ends_at.0)
+            }
+            Self::NoDeposit => write!(f, "Deposit must be attached"),
+            Self::NotEnoughBalance { free, required } => write!(
+                f,
+                "Not enough free balance. Free: {}, required: {}",
+                free.0, required.0
+            ),
+            Self::NotEnoughBalanceForBids { free, required } => write!(
+                f,
+                "Not enough free balance for all bids. Free: {}, required: {}",
+                free.0, required.0
+            ),
+            Self::StorageNotCovered { required, attached } => write!(
+                f,
+                "Attached deposit does not cover storage. Required: {}, attached: {}",
+                required.0, attached.0
+            ),
+            Self::InvalidDescription => write!(
+                f,
+                "Description must be from 1 to {} bytes",
+                MAX_DESCRIPTION_LENGTH
+            ),
+            Self::ZeroBudget => write!(f, "Budget must be positive"),
+            Self::ProcurementNotFound { request_id } => {
+                write!(f, "Procurement request {} does not exist", request_id)
+            }
+            Self::OwnRequest => write!(f, "Buyer can not make ask for his request"),
+            Self::ZeroAsk => write!(f, "Ask must be positive"),
+            Self::AskAboveBudget { budget, amount } => write!(
+                f,
+                "Ask must not exceed the budget {}. Actual: {}",
+                budget.0, amount.0
+            ),
+            Self::SmallerAskExists { .. } => {
+                write!(f, "A smaller ask for this request already exists")
+            }
+            Self::InvalidPublicKey => write!(f, "Invalid public key"),
+            Self::BidKeyNotFound { account_id } => {
+                write!(f, "Account {} has not registered a bid key", account_id)
+            }
+            Self::SignedBidExpired => write!(f, "Signed bid has expired"),
+            Self::InvalidNonce { last_nonce, nonce } => write!(
+                f,
+                "Nonce must be bigger than {}. Actual: {}",
+                last_nonce.0, nonce.0
+            ),
+            Self::InvalidSignature => write!(f, "Invalid signature"),
+            Self::Insolvent {
+                liabilities,
+                balance,
+            } => write!(
+                f,
+                "Contract is insolvent. Liabilities: {}, available balance: {}",
+                liabilities.0, balance.0
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(account_id: &str) -> AccountId {
+        AccountId::new_unchecked(account_id.to_string())
+    }
+
+    #[test]
+    fn test_code_matches_json() {
+        let errors = vec![
+            AuctionError::MissingRole {
+                account_id: account("alice.near"),
+                roles: vec![Role::Admin],
+            },
+            AuctionError::ContractPaused,
+            AuctionError::ItemNotFound {
+                item_hash: "hash".to_string(),
+            },
+            AuctionError::NotEnoughBalanceForBids {
+                free: U128(1),
+                required: U128(2),
+            },
+            AuctionError::InvalidNonce {
+                last_nonce: U64(1),
+                nonce: U64(1),
+            },
+        ];
+
+        for error in errors {
+            let json = serde_json::to_value(&error).unwrap();
+            assert_eq!(json["code"], error.code());
+        }
+    }
+
+    #[test]
+    fn test_messages() {
+        assert_eq!(
+            AuctionError::MissingRole {
+                account_id: account("alice.near"),
+                roles: vec![Role::Admin, Role::Guardian],
+            }
+            .to_string(),
+            "alice.near does not have any of the required roles: [Admin, Guardian]"
+        );
+        assert_eq!(
+            AuctionError::FieldTooLong {
+                field: "title".to_string(),
+                max_length: U64(5),
+                length: U64(6),
+            }
+            .to_string(),
+            "Item title is too long. Max length: 5, actual: 6"
+        );
+        assert_eq!(
+            AuctionError::InvalidInviteCodeHash.to_string(),
+            "Invite code hash must be 32 bytes long"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let error = AuctionError::BelowMinBid {
+            min_bid: U128(10),
+            amount: U128(5),
+        };
+        let json = r#"{"code":"below_min_bid","min_bid":"10","amount":"5"}"#;
+
+        assert_eq!(serde_json::to_string(&error).unwrap(), json);
+        assert_eq!(serde_json::from_str::<AuctionError>(json).unwrap(), error);
+    }
+
+    #[test]
+    #[should_panic(expected = "auction_closed: Auction is closed. Try again later")]
+    fn test_panic_message_starts_with_code() {
+        AuctionError::AuctionClosed.panic();
+    }
+}
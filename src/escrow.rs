@@ -0,0 +1,169 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, AccountId, Promise};
+
+use crate::errors::AuctionError;
+use crate::events::AuctionEvent;
+use crate::solvency::pay_for_storage;
+use crate::views::EscrowView;
+use crate::{Auction, AuctionExt, Money};
+
+/// Money that a bidder keeps in the contract
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Default, PartialEq)]
+pub struct Escrow {
+    pub free: Money,   // can be withdrawn or used for new bids
+    pub locked: Money, // backs the bidder's leading bids
+}
+
+#[near_bindgen]
+impl Auction {
+    /// add attached deposit to caller's free balance. The deposit that opens the balance also
+    /// pays for its storage, that part is not credited. The balance stays stored when it is
+    /// emptied, so its storage is paid only once
+    ///
+    /// # Panics
+    ///
+    /// * deposit is not attached
+    /// * deposit does not cover storage of a new balance
+    /// * contract must not be paused
+    #[payable]
+    pub fn deposit(&mut self) {
+        self.assert_not_paused();
+        if env::attached_deposit() == 0 {
+            AuctionError::NoDeposit.panic()
+        }
+
+        self.deposit_attached(&env::predecessor_account_id());
+    }
+
+    /// send money from caller's free balance back to the caller. Works when the contract is paused
+    ///
+    /// # Arguments
+    ///
+    /// * `amount` - amount of money. Whole free balance if not set
+    ///
+    /// return withdrawn amount
+    ///
+    /// # Panics
+    ///
+    /// * free balance is less than the amount
+    pub fn withdraw(&mut self, amount: Option<U128>) -> U128 {
+        let account_id = env::predecessor_account_id();
+        let mut escrow = self.escrow.get(&account_id).unwrap_or_default();
+        let amount = amount.map_or(escrow.free, |amount| amount.0);
+
+        if escrow.free < amount {
+            AuctionError::NotEnoughBalance {
+                free: U128(escrow.free),
+                required: U128(amount),
+            }
+            .panic()
+        }
+
+        escrow.free -= amount;
+        self.escrow_total.free -= amount;
+        self.save_escrow(&account_id, escrow);
+
+        if amount > 0 {
+            Promise::new(account_id.clone()).transfer(amount);
+
+            AuctionEvent::Withdrawn {
+                account_id,
+                amount: U128(amount),
+            }
+            .emit();
+        }
+
+        U128(amount)
+    }
+
+    pub fn get_balance(&self, account_id: AccountId) -> EscrowView {
+        EscrowView::from(self.escrow.get(&account_id).unwrap_or_default())
+    }
+}
+
+impl Auction {
+    /// add attached deposit to the account's free balance. The deposit that opens the balance
+    /// pays for its storage first, like `deposit` does
+    ///
+    /// # Panics
+    ///
+    /// * deposit does not cover storage of a new balance
+    pub(crate) fn deposit_attached(&mut self, account_id: &AccountId) {
+        let mut amount = env::attached_deposit();
+        if amount == 0 {
+            return;
+        }
+
+        if !self.escrow.contains_key(account_id) {
+            let storage_usage = env::storage_usage();
+            self.escrow.insert(account_id, &Escrow::default());
+            amount = pay_for_storage(storage_usage);
+        }
+
+        self.credit(account_id, amount);
+    }
+
+    /// add money to the account's free balance
+    pub(crate) fn credit(&mut self, account_id: &AccountId, amount: Money) {
+        let mut escrow = self.escrow.get(account_id).unwrap_or_default();
+        escrow.free += amount;
+        self.escrow_total.free += amount;
+        self.save_escrow(account_id, escrow);
+
+        AuctionEvent::Deposited {
+            account_id: account_id.clone(),
+            amount: U128(amount),
+        }
+        .emit();
+    }
+
+    /// move money from free balance to locked one
+    ///
+    /// # Panics
+    ///
+    /// * free balance is less than the amount
+    pub(crate) fn lock(&mut self, account_id: &AccountId, amount: Money) {
+        let mut escrow = self.escrow.get(account_id).unwrap_or_default();
+
+        if escrow.free < amount {
+            AuctionError::NotEnoughBalance {
+                free: U128(escrow.free),
+                required: U128(amount),
+            }
+            .panic()
+        }
+
+        escrow.free -= amount;
+        escrow.locked += amount;
+        self.escrow_total.free -= amount;
+        self.escrow_total.locked += amount;
+        self.save_escrow(account_id, escrow);
+    }
+
+    /// move money from locked balance back to free one
+    pub(crate) fn unlock(&mut self, account_id: &AccountId, amount: Money) {
+        let mut escrow = self.escrow.get(account_id).unwrap_or_default();
+        escrow.locked -= amount;
+        escrow.free += amount;
+        self.escrow_total.locked -= amount;
+        self.escrow_total.free += amount;
+        self.save_escrow(account_id, escrow);
+    }
+
+    /// take locked money of a winner to pay for a lot
+    pub(crate) fn spend_locked(&mut self, account_id: &AccountId, amount: Money) {
+        let mut escrow = self.escrow.get(account_id).unwrap_or_default();
+        escrow.locked -= amount;
+        self.escrow_total.locked -= amount;
+        self.save_escrow(account_id, escrow);
+    }
+
+    /// balances are opened only by `deposit_attached`, which charges their storage, and are
+    /// kept when they are emptied
+    fn save_escrow(&mut self, account_id: &AccountId, escrow: Escrow) {
+        if escrow != Escrow::default() || self.escrow.contains_key(account_id) {
+            self.escrow.insert(account_id, &escrow);
+        }
+    }
+}
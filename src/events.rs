@@ -0,0 +1,123 @@
+use near_sdk::env;
+use near_sdk::json_types::{Base64VecU8, U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::AccountId;
+
+use crate::roles::Role;
+use crate::supplier::ItemHash;
+
+const EVENT_STANDARD: &str = "auction";
+const EVENT_VERSION: &str = "1.0.0";
+
+/// Events that the contract logs in the NEP-297 format (`EVENT_JSON:{...}`)
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum AuctionEvent {
+    ItemAdded {
+        supplier: AccountId,
+        item_hash: ItemHash,
+        round: u64,
+    },
+    ItemUpdated {
+        supplier: AccountId,
+        item_hash: ItemHash,
+    },
+    ItemRemoved {
+        supplier: AccountId,
+        item_hash: ItemHash,
+    },
+    AuctionCancelled {
+        round: u64,
+        reason: String,
+    },
+    Paused {
+        account_id: AccountId,
+    },
+    Unpaused {
+        account_id: AccountId,
+    },
+    RoleGranted {
+        account_id: AccountId,
+        role: Role,
+    },
+    RoleRevoked {
+        account_id: AccountId,
+        role: Role,
+    },
+    ItemDelisted {
+        supplier: AccountId,
+        item_hash: ItemHash,
+        reason: String,
+    },
+    AccountBanned {
+        account_id: AccountId,
+        reason: String,
+    },
+    AccountUnbanned {
+        account_id: AccountId,
+    },
+    Deposited {
+        account_id: AccountId,
+        amount: U128,
+    },
+    Withdrawn {
+        account_id: AccountId,
+        amount: U128,
+    },
+    ProcurementCreated {
+        buyer: AccountId,
+        request_id: ItemHash,
+        budget: U128,
+    },
+    CandleClosed {
+        round: u64,
+        close_time: U64,
+        random_seed: Base64VecU8,
+    },
+}
+
+#[derive(Serialize)]
+struct EventLog<'a> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event: &'a AuctionEvent,
+}
+
+impl AuctionEvent {
+    /// log event so indexers and frontend can track contract's state changes
+    pub fn emit(&self) {
+        let log = EventLog {
+            standard: EVENT_STANDARD,
+            version: EVENT_VERSION,
+            event: self,
+        };
+
+        env::log_str(&format!(
+            "EVENT_JSON:{}",
+            serde_json::to_string(&log).unwrap()
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::get_logs;
+
+    #[test]
+    fn test_emit() {
+        AuctionEvent::ItemRemoved {
+            supplier: AccountId::new_unchecked("bob.near".to_string()),
+            item_hash: "hash".to_string(),
+        }
+        .emit();
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"auction","version":"1.0.0","event":"item_removed","data":{"supplier":"bob.near","item_hash":"hash"}}"#
+            ]
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::Vector;
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::views::BidRecordView;
+use crate::{Auction, AuctionExt, ItemHash, Money, StorageKey, DEFAULT_PAGE_LIMIT};
+
+/// State of a bid from the bidder's point of view
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, Copy, PartialEq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum BidStatus {
+    Leading,  // the biggest bid for the lot at the moment
+    Outbid,   // somebody made a bigger bid. Money is unlocked now or, in candle auctions, later
+    Won,      // lot has been sold to the bidder
+    Refunded, // bid has not won, money is free in the bidder's balance
+    Pending,  // bundle or unit bid that competes with other bids when the auction is finished
+}
+
+/// What a bid has been made for
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BidKind {
+    Single,                  // bid for one lot that outbids the previous one
+    Bundle,                  // part of a bid for several lots, the amount is for all of them
+    Units { quantity: U64 }, // bid for units of a multi-unit lot, the amount is for all of them
+}
+
+/// Every bid that has been made for a lot
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct BidRecord {
+    pub account_id: AccountId,
+    pub item_hash: ItemHash,
+    pub amount: Money,
+    pub timestamp: u64,
+    pub block_height: u64,
+    pub status: BidStatus,
+    pub kind: BidKind,
+}
+
+/// Position of a bid record in the history of a lot
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct BidRef {
+    pub item_hash: ItemHash,
+    pub index: u64,
+}
+
+impl Auction {
+    /// save bid to the histories of the lot and the bidder. Previous leading bid becomes outbid
+    ///
+    /// # Arguments
+    ///
+    /// * `account_id` - bidder
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `amount` - amount of the bid
+    pub(crate) fn record_bid(
+        &mut self,
+        account_id: &AccountId,
+        item_hash: &ItemHash,
+        amount: Money,
+    ) {
+        if let Some(mut history) = self.bid_history.get(item_hash) {
+            // bundle and unit bids do not outbid anybody so the previous single bid is looked for
+            let last_single = (0..history.len())
+                .rev()
+                .map(|index| (index, history.get(index).unwrap()))
+                .find(|(_, record)| record.kind == BidKind::Single);
+
+            if let Some((index, mut last_bid)) = last_single {
+                if last_bid.status == BidStatus::Leading {
+                    last_bid.status = BidStatus::Outbid;
+                    history.replace(index, &last_bid);
+                }
+            }
+        }
+
+        self.push_bid_record(
+            account_id,
+            item_hash,
+            amount,
+            BidKind::Single,
+            BidStatus::Leading,
+        );
+    }
+
+    /// save bundle or unit bid to the histories of the lot and the bidder. Its status is pending until
+    /// the auction is finished
+    ///
+    /// # Arguments
+    ///
+    /// * `account_id` - bidder
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `amount` - amount of the whole bid
+    /// * `kind` - what the bid has been made for
+    ///
+    /// return index of the record in the history of the lot
+    pub(crate) fn record_pending_bid(
+        &mut self,
+        account_id: &AccountId,
+        item_hash: &ItemHash,
+        amount: Money,
+        kind: BidKind,
+    ) -> u64 {
+        self.push_bid_record(account_id, item_hash, amount, kind, BidStatus::Pending)
+    }
+
+    /// change status of the bid after an auction has been finished
+    ///
+    /// # Arguments
+    ///
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `index` - index of the record in the history of the lot
+    /// * `status` - `Won` or `Refunded`
+    pub(crate) fn settle_bid(&mut self, item_hash: &ItemHash, index: u64, status: BidStatus) {
+        if let Some(mut history) = self.bid_history.get(item_hash) {
+            if let Some(mut record) = history.get(index) {
+                record.status = status;
+                history.replace(index, &record);
+            }
+        }
+    }
+
+    /// return index of the record in the history of the lot
+    fn push_bid_record(
+        &mut self,
+        account_id: &AccountId,
+        item_hash: &ItemHash,
+        amount: Money,
+        kind: BidKind,
+        status: BidStatus,
+    ) -> u64 {
+        let mut history = self.bid_history.get(item_hash).unwrap_or_else(|| {
+            Vector::new(StorageKey::BidHistory {
+                lot_hash: env::sha256_array(item_hash.as_bytes()),
+            })
+        });
+
+        history.push(&BidRecord {
+            account_id: account_id.clone(),
+            item_hash: item_hash.clone(),
+            amount,
+            timestamp: env::block_timestamp(),
+            block_height: env::block_height(),
+            status,
+            kind,
+        });
+
+        let mut account_bids = self.account_bids.get(account_id).unwrap_or_else(|| {
+            Vector::new(StorageKey::AccountBids {
+                account_hash: env::sha256_array(account_id.as_bytes()),
+            })
+        });
+
+        account_bids.push(&BidRef {
+            item_hash: item_hash.clone(),
+            index: history.len() - 1,
+        });
+
+        // vectors keep their lengths inside themselves so they have to be saved after each push
+        self.bid_history.insert(item_hash, &history);
+        self.account_bids.insert(account_id, &account_bids);
+
+        history.len() - 1
+    }
+
+    /// change statuses of the lot's bids after an auction has been finished. Leading bid gets
+    /// `leading_status`, outbid ones become refunded
+    ///
+    /// # Arguments
+    ///
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `leading_status` - `Won` if the lot has been sold, `Refunded` otherwise
+    pub(crate) fn settle_bid_history(&mut self, item_hash: &ItemHash, leading_status: BidStatus) {
+        if let Some(mut history) = self.bid_history.get(item_hash) {
+            for index in 0..history.len() {
+                let mut record = history.get(index).unwrap();
+
+                let status = match record.status {
+                    BidStatus::Leading => leading_status,
+                    BidStatus::Outbid => BidStatus::Refunded,
+                    status => status,
+                };
+
+                if status != record.status {
+                    record.status = status;
+                    history.replace(index, &record);
+                }
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl Auction {
+    /// return bids that the account has made, oldest first
+    ///
+    /// # Arguments
+    ///
+    /// * `account_id` - bidder
+    /// * `from_index` - index of the first bid. 0 if not set
+    /// * `limit` - max amount of bids. `DEFAULT_PAGE_LIMIT` if not set
+    pub fn get_bids_by_account(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<BidRecordView> {
+        let account_bids = match self.account_bids.get(&account_id) {
+            Some(account_bids) => account_bids,
+            None => return vec![],
+        };
+
+        account_bids
+            .iter()
+            .skip(from_index.map_or(0, |index| index.0) as usize)
+            .take(limit.map_or(DEFAULT_PAGE_LIMIT, |limit| limit.0) as usize)
+            .filter_map(|bid_ref| self.bid_history.get(&bid_ref.item_hash)?.get(bid_ref.index))
+            .map(BidRecordView::from)
+            .collect()
+    }
+
+    /// return bids that have been made for the lot, oldest first
+    ///
+    /// # Arguments
+    ///
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `from_index` - index of the first bid. 0 if not set
+    /// * `limit` - max amount of bids. `DEFAULT_PAGE_LIMIT` if not set
+    pub fn get_bid_history(
+        &self,
+        item_hash: ItemHash,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<BidRecordView> {
+        let history = match self.bid_history.get(&item_hash) {
+            Some(history) => history,
+            None => return vec![],
+        };
+
+        history
+            .iter()
+            .skip(from_index.map_or(0, |index| index.0) as usize)
+            .take(limit.map_or(DEFAULT_PAGE_LIMIT, |limit| limit.0) as usize)
+            .map(BidRecordView::from)
+            .collect()
+    }
+}
@@ -1,21 +1,105 @@
-mod helper;
+pub mod access;
+pub mod allocation;
+mod bonds;
+pub mod bundles;
+mod cancellation;
+pub mod candle;
+pub mod errors;
+pub mod escrow;
+pub mod events;
+pub mod history;
+pub mod metadata;
+pub mod moderation;
+mod pause;
+pub mod procurement;
+pub mod roles;
+pub mod rounds;
+pub mod sales;
+pub mod signed_bids;
+pub mod solvency;
 pub mod supplier;
+pub mod units;
+pub mod validation;
+pub mod views;
 
-use std::collections::HashMap;
+#[cfg(test)]
+mod test_utils;
 
-use helper::Helper;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LookupMap, UnorderedMap, Vector};
-use near_sdk::serde::Serialize;
-use near_sdk::{env, PanicOnDefault};
+use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, BorshStorageKey, CryptoHash, PanicOnDefault};
 use near_sdk::{near_bindgen, AccountId, Promise};
 
+use crate::access::AccessPolicy;
+use crate::bundles::BundleBid;
+use crate::candle::CandleEnding;
+use crate::errors::AuctionError;
+use crate::escrow::Escrow;
+use crate::events::AuctionEvent;
+use crate::history::{BidRecord, BidRef};
+use crate::metadata::ItemMetadata;
+use crate::moderation::ModerationRecord;
+use crate::procurement::{Ask, ProcurementRequest};
+use crate::roles::Role;
+use crate::rounds::{LotOutcome, ProcurementOutcome, RoundRecord, RoundStatus};
+use crate::sales::{Purchase, Sale};
+use crate::signed_bids::BidKey;
 use crate::supplier::Supplier;
+use crate::units::{Clearing, UnitBid};
+use crate::views::{LotFilter, LotView};
+
+const DEFAULT_PAGE_LIMIT: u64 = 50;
+const DEFAULT_BATCH_LIMIT: u64 = 100;
 
 type Item = String;
 type Money = u128;
 type ItemHash = String;
 
+/// Prefixes of the collections that are created for each lot, account or round. Ids are hashed,
+/// so the prefixes have the same length and do not depend on how many collections exist
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    BidHistory {
+        lot_hash: CryptoHash,
+    },
+    AccountBids {
+        account_hash: CryptoHash,
+    },
+    Purchases {
+        account_hash: CryptoHash,
+    },
+    Sales {
+        account_hash: CryptoHash,
+    },
+    UnitBids {
+        lot_hash: CryptoHash,
+    },
+    AskHistory {
+        request_hash: CryptoHash,
+    },
+    RoundProcurements {
+        round: u64,
+    },
+    RoundLots {
+        round: u64,
+    },
+    RoundRefunds {
+        round: u64,
+    },
+    SupplierItems {
+        account_hash: CryptoHash,
+        round: u64,
+    },
+    SupplierContents {
+        account_hash: CryptoHash,
+        round: u64,
+    },
+    Allowlist {
+        round: u64,
+    },
+}
+
 /// Representation of a user's bid that contains information about account id and amount of bid
 #[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
 pub struct Bid {
@@ -23,16 +107,6 @@ pub struct Bid {
     pub bid: Money,
 }
 
-/// Representation of a lot for frontend
-#[derive(Serialize)]
-struct Lot {
-    item_hash: ItemHash,
-    item: Item,
-    supplier: AccountId,
-    winner: AccountId,
-    current_bid: u128,
-}
-
 impl Bid {
     pub fn new(account_id: &AccountId, bid: &Money) -> Self {
         Self {
@@ -47,12 +121,45 @@ impl Bid {
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Auction {
     pub suppliers: UnorderedMap<AccountId, Supplier>, // who want to sell item
+    pub lots: UnorderedMap<ItemHash, AccountId>,      // supplier of each lot in the current auction
     pub items_and_bids: UnorderedMap<ItemHash, Bid>,  // current bid for each item
-    pub users_bids: UnorderedMap<AccountId, Money>, // whole sum of all bids for each user (e.g. user wants to buy item_1 and item_2. He bids item_1 = 1 token, item_2 = 1 token. Sum will be 2 tokens)
-    pub winners_items: LookupMap<AccountId, Vector<Item>>, // each item winner
+    pub bundle_bids: Vector<BundleBid>,               // bids for several lots together
+    pub unit_bids: UnorderedMap<ItemHash, Vector<UnitBid>>, // bids for units of each multi-unit lot
+    pub procurements: UnorderedMap<ItemHash, ProcurementRequest>, // buyers' requests that sellers ask for
+    pub escrow: LookupMap<AccountId, Escrow>,        // free and locked money of each bidder
+    escrow_total: Escrow, // sums of free and locked money of all bidders
+    pub bid_keys: LookupMap<AccountId, BidKey>,      // keys that sign bids submitted by relayers
+    pub winners_items: LookupMap<AccountId, Vector<Purchase>>, // items that each winner has bought in all auctions
+    pub bid_history: LookupMap<ItemHash, Vector<BidRecord>>, // every bid for each lot
+    pub ask_history: LookupMap<ItemHash, Vector<Ask>>, // every ask for each procurement request
+    pub account_bids: LookupMap<AccountId, Vector<BidRef>>, // every bid of each user
+    pub supplier_sales: LookupMap<AccountId, Vector<Sale>>, // items that each supplier has sold in all auctions
+    pub paid_proceeds: LookupMap<AccountId, Money>, // money that has been sent to each supplier
+    pub rounds: Vector<RoundRecord>, // archive of completed auctions
+    cancellation_refunds: Vector<(AccountId, Money)>, // money returned by the unfinished cancellation
+    cancellation_lots: Vector<LotOutcome>, // lots archived by the unfinished cancellation
+    cancellation_procurements: Vector<ProcurementOutcome>, // procurements archived by the unfinished cancellation
+
+    owner_id: AccountId,
+    roles: UnorderedMap<AccountId, Vec<Role>>, // permissions granted by admins
+    paused: bool, // state-changing methods except refunds are blocked
+    blocklist: UnorderedSet<AccountId>, // accounts that can not add items and make bids
+    moderation_log: Vector<ModerationRecord>,
+
+    access_policy: AccessPolicy, // who can take part in the current auction
+    restrict_suppliers: bool,    // is the access policy checked for suppliers too
+    allowlist: UnorderedSet<AccountId>,
+
+    listing_bond: Money,    // deposit that suppliers pay for each lot
+    forfeited_bonds: Money, // bonds of the lots delisted in the current auction
+    bonds_total: Money,     // bonds of the lots that are listed in the current auction
+    cancellation_reason: Option<String>, // set while the current auction is being cancelled
+    candle_ending: Option<CandleEnding>, // set if the current auction is closed at a random moment
 
     auction_is_open: bool, // does suppliers can add new items or buyers can add new bids for the current auction
-    helper: Helper,
+    round: u64,            // id of the current (or the last finished) auction
+    round_started_at: u64, // when the current (or the last finished) auction has been started
+    next_lot_nonce: u64,   // monotonic counter that makes lot ids unique
 }
 
 #[near_bindgen]
@@ -61,11 +168,41 @@ impl Auction {
     pub fn new() -> Self {
         Self {
             suppliers: UnorderedMap::new(b"suppliers".to_vec()),
+            lots: UnorderedMap::new(b"lots".to_vec()),
             items_and_bids: UnorderedMap::new(b"items_and_bids".to_vec()),
-            users_bids: UnorderedMap::new(b"users_bids".to_vec()),
+            bundle_bids: Vector::new(b"bundle_bids".to_vec()),
+            unit_bids: UnorderedMap::new(b"unit_bids".to_vec()),
+            procurements: UnorderedMap::new(b"procurements".to_vec()),
+            escrow: LookupMap::new(b"escrow".to_vec()),
+            escrow_total: Escrow::default(),
+            bid_keys: LookupMap::new(b"bid_keys".to_vec()),
             winners_items: LookupMap::new(b"winners_items".to_vec()),
+            bid_history: LookupMap::new(b"bid_history".to_vec()),
+            ask_history: LookupMap::new(b"ask_history".to_vec()),
+            account_bids: LookupMap::new(b"account_bids".to_vec()),
+            supplier_sales: LookupMap::new(b"supplier_sales".to_vec()),
+            paid_proceeds: LookupMap::new(b"paid_proceeds".to_vec()),
+            rounds: Vector::new(b"rounds".to_vec()),
+            cancellation_refunds: Vector::new(b"cancellation_refunds".to_vec()),
+            cancellation_lots: Vector::new(b"cancellation_lots".to_vec()),
+            cancellation_procurements: Vector::new(b"cancellation_procurements".to_vec()),
+            owner_id: env::predecessor_account_id(),
+            roles: UnorderedMap::new(b"roles".to_vec()),
+            paused: false,
+            blocklist: UnorderedSet::new(b"blocklist".to_vec()),
+            moderation_log: Vector::new(b"moderation_log".to_vec()),
+            access_policy: AccessPolicy::Open,
+            restrict_suppliers: false,
+            allowlist: UnorderedSet::new(StorageKey::Allowlist { round: 1 }),
+            listing_bond: 0,
+            forfeited_bonds: 0,
+            bonds_total: 0,
+            cancellation_reason: None,
+            candle_ending: None,
             auction_is_open: false,
-            helper: Helper::new(),
+            round: 0,
+            round_started_at: 0,
+            next_lot_nonce: 0,
         }
     }
 
@@ -74,306 +211,587 @@ impl Auction {
     /// # Panics
     ///
     ///  * if an auction has not been opened yet
+    ///  * if the previous auction is being cancelled
+    ///  * if the contract is paused
+    ///  * if caller is not an admin
     pub fn start_new_auction(&mut self) {
-        assert!(!self.auction_is_open, "Auction is already opened");
+        self.assert_role(&[Role::Admin]);
+        self.assert_not_paused();
+        if self.auction_is_open {
+            AuctionError::AuctionAlreadyOpened.panic()
+        }
+        if self.cancellation_reason.is_some() {
+            AuctionError::CancellationInProgress.panic()
+        }
         self.auction_is_open = true;
+        self.round += 1;
+        self.round_started_at = env::block_timestamp();
     }
 
-    /// return all items that caller won
+    /// return all items that caller won. See `get_purchases` for prices, suppliers and rounds
     pub fn get_items(&self, account_id: String) -> Vec<String> {
         let items = self
             .winners_items
             .get(&AccountId::new_unchecked(account_id));
 
         match items {
-            Some(itms) => itms.to_vec(),
+            Some(itms) => itms.iter().map(|purchase| purchase.item).collect(),
             None => Vec::<String>::new(),
         }
     }
 
-    /// return all available lots
-    pub fn get_lots(&self) -> String {
-        let mut lots = Vec::<Lot>::new();
-
-        for (_, supplier) in self.suppliers.iter() {
-            for (item_hash, item) in supplier.items.iter() {
-                let winner_bid = self.items_and_bids.get(&item_hash).unwrap_or_else(|| {
-                    Bid::new(&AccountId::new_unchecked("0".to_string()), &item.min_bid)
-                });
+    /// return a page of available lots
+    ///
+    /// # Arguments
+    ///
+    /// * `from_index` - index of the first lot to look at. 0 if not set
+    /// * `limit` - how many lots to look at. `DEFAULT_PAGE_LIMIT` if not set
+    /// * `filter` - conditions that returned lots have to satisfy
+    ///
+    /// `from_index` and `limit` address all lots, not only filtered ones, so a view never reads more
+    /// than `limit` lots and a filtered page can contain less than `limit` lots
+    pub fn get_lots(
+        &self,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+        filter: Option<LotFilter>,
+    ) -> Vec<LotView> {
+        let filter = filter.unwrap_or_default();
+
+        self.lots
+            .keys()
+            .skip(from_index.map_or(0, |index| index.0) as usize)
+            .take(limit.map_or(DEFAULT_PAGE_LIMIT, |limit| limit.0) as usize)
+            .filter_map(|item_hash| self.get_lot(item_hash))
+            .filter(|lot| filter.matches(lot))
+            .collect()
+    }
 
-                lots.push(Lot {
-                    item: item.itself,
-                    supplier: supplier.id.clone(),
-                    current_bid: winner_bid.bid,
-                    item_hash,
-                    winner: winner_bid.account_id,
-                })
-            }
-        }
+    /// return lot with such id if it is in the current auction
+    ///
+    /// # Arguments
+    ///
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    pub fn get_lot(&self, item_hash: ItemHash) -> Option<LotView> {
+        let supplier = self.suppliers.get(&self.lots.get(&item_hash)?)?;
+        let item = supplier.items.get(&item_hash)?;
+        let bid = self.items_and_bids.get(&item_hash);
 
-        serde_json::to_string(&lots).unwrap()
+        Some(LotView::new(item_hash, item, &supplier.id, bid))
     }
 
-    /// clear all data except won items after an auction has been finished
-    pub fn clear_data(&mut self) {
-        self.suppliers.clear();
-        self.items_and_bids.clear();
-        self.users_bids.clear();
+    /// return amount of lots in the current auction
+    pub fn get_lots_count(&self) -> U64 {
+        U64(self.lots.len())
     }
 
-    /// make bid for item
+    /// make bid for item. The bid is locked in caller's balance until somebody outbids it
+    /// or the auction is finished
     ///
     /// # Arguments
     ///
-    /// * `item_hash` - hash calculated from an item through the SHA256 algorithm
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `amount` - bid. Attached deposit is added to caller's free balance before the bid is locked
     ///
     /// # Panics
     ///
     ///  * auction must be started
     ///  * supplier can not make bid for his own item
-    ///  * attached deposit must be more than item's minimal bid
+    ///  * amount must be more than item's minimal bid
     ///  * item must exists
     ///  * bid has to be bigger than previous
+    ///  * free balance must cover the bid (or the raise if caller already leads)
+    ///  * attached deposit must cover storage of a new balance (see `deposit`)
+    ///  * contract must not be paused
+    ///  * bidder must not be banned
+    ///  * bidder must be allowed by the access policy
     #[payable]
-    pub fn make_bid(&mut self, item_hash: &ItemHash) {
-        assert!(self.auction_is_open, "Auction is closed. Try again later");
+    pub fn make_bid(&mut self, item_hash: &ItemHash, amount: U128) {
+        let bidder = env::predecessor_account_id();
+        self.assert_can_bid(&bidder);
 
-        assert!(
-            !self.does_supplier_make_bid_for_his_item(&item_hash),
-            "Supplier can not make bid for his items"
-        );
+        self.deposit_attached(&bidder);
+
+        self.place_bid(&bidder, item_hash, amount.0);
+    }
+
+    /// make bids for several items at once. Either all bids are made or none of them
+    ///
+    /// # Arguments
+    ///
+    /// * `bids` - pairs of lot id (see `supplier::get_lot_id`) and bid. Attached deposit is added
+    /// to caller's free balance before the bids are locked
+    ///
+    /// # Panics
+    ///
+    ///  * bids are empty or contain the same item twice
+    ///  * free balance must cover all bids
+    ///  * attached deposit must cover storage of a new balance (see `deposit`)
+    ///  * any bid is invalid (see `make_bid`)
+    #[payable]
+    pub fn make_bids(&mut self, bids: Vec<(ItemHash, U128)>) {
+        let bidder = env::predecessor_account_id();
+        self.assert_can_bid(&bidder);
 
-        let mut item_exists = false;
+        if bids.is_empty() {
+            AuctionError::EmptyBids.panic()
+        }
+
+        self.deposit_attached(&bidder);
 
-        for mut supplier in self.suppliers.values() {
-            let (can_bid, min_bid) = supplier.bid_can_be_done(&item_hash, &env::attached_deposit());
-            if !can_bid {
-                panic!(
-                    "This item has {} minimum bid. Actual: {}",
-                    min_bid,
-                    env::attached_deposit()
-                )
+        let mut required: Money = 0;
+
+        for (index, (item_hash, amount)) in bids.iter().enumerate() {
+            if bids[..index].iter().any(|(other, _)| other == item_hash) {
+                AuctionError::DuplicateItem {
+                    item_hash: item_hash.clone(),
+                }
+                .panic()
             }
 
-            if min_bid >= supplier::DEFAULT_MIN_BID {
-                item_exists = true;
-                break;
+            // raising own bid locks only the difference
+            required += amount.0.saturating_sub(self.locked_for_lot(&bidder, item_hash));
+        }
+
+        let free = self.escrow.get(&bidder).unwrap_or_default().free;
+        if free < required {
+            AuctionError::NotEnoughBalanceForBids {
+                free: U128(free),
+                required: U128(required),
             }
+            .panic()
         }
 
-        assert!(!item_exists, "Item with hash {} does not exist", item_hash);
+        for (item_hash, amount) in bids.iter() {
+            self.place_bid(&bidder, item_hash, amount.0);
+        }
+    }
 
-        if let Some(exists_bid) = self.items_and_bids.get(&item_hash) {
-            assert!(
-                env::attached_deposit() > exists_bid.bid,
-                "A bigger bid for this item already exists"
-            );
+    /// execute an auction process. Only admins can call it, otherwise anybody could pick the block
+    /// whose random seed gives a favourable close time of a candle auction. The block producer
+    /// still knows the seed before the block is published and can delay the call, so candle
+    /// auctions trust validators not to collude with bidders
+    ///
+    /// # Panics
+    ///
+    /// * auction must not be finished
+    /// * contract must not be paused
+    /// * ending period of a candle auction must be over
+    /// * caller must be an admin
+    pub fn produce_auction(&mut self) {
+        self.assert_role(&[Role::Admin]);
+        self.assert_not_paused();
+        if !self.auction_is_open {
+            AuctionError::AuctionFinished.panic()
         }
 
-        self.items_and_bids.insert(
-            &item_hash,
-            &Bid::new(&env::predecessor_account_id(), &env::attached_deposit()),
-        );
+        self.auction_is_open = false;
+        self.close_candle();
 
-        let bid = self
-            .users_bids
-            .get(&env::predecessor_account_id())
-            .unwrap_or_default()
-            + env::attached_deposit();
+        let mut sales: Vec<(ItemHash, Clearing)> = self
+            .determine_winners()
+            .into_iter()
+            .map(|(item_hash, bid)| (item_hash, Clearing::from(bid)))
+            .collect();
+        sales.extend(self.clear_unit_lots());
 
-        self.users_bids.insert(&env::predecessor_account_id(), &bid);
+        let lot_outcomes = self.collect_lot_outcomes(&sales);
+
+        for (item_hash, clearing) in sales.iter() {
+            self.produce_exchange(item_hash, clearing);
+        }
+
+        let procurement_outcomes = self.settle_procurements();
+
+        self.return_unsold_bonds();
+
+        // the contract does not keep any money from sales, only bonds of delisted lots
+        let fees = self.forfeited_bonds;
+        // money of lost bids has been unlocked in bidders' balances so nothing is refunded
+        let refunds = Vector::new(StorageKey::RoundRefunds { round: self.round });
+        self.archive_round(
+            lot_outcomes,
+            procurement_outcomes,
+            refunds,
+            fees,
+            RoundStatus::Completed,
+        );
+        self.clear_data();
+    }
+
+    /// add item to an auction as a supplier
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - representation of an item
+    /// * `min_bid` - minimal bid for this item. Will be replaced to 1 if 0
+    /// * `metadata` - optional title, description, media, category, condition and extra JSON of the item
+    ///
+    /// Attached deposit must be equal to the listing bond (see `get_listing_bond`). The bond is returned
+    /// when the item is sold, withdrawn or left unsold and goes to the owner when the item is delisted
+    ///
+    /// return id of the created lot. It is derived from the supplier, the auction round and a nonce,
+    /// so it does not depend on the item itself
+    ///
+    /// # Panics
+    ///  * auction must be opened
+    ///  * supplier must not add the same item twice
+    ///  * metadata must fit size limits (see `ItemMetadata::assert_valid`)
+    ///  * contract must not be paused
+    ///  * supplier must not be banned
+    ///  * supplier must be allowed by the access policy if it restricts suppliers
+    ///  * attached deposit must be equal to the listing bond
+    #[payable]
+    pub fn add_item_to_auction(
+        &mut self,
+        item: &Item,
+        min_bid: U128,
+        metadata: Option<ItemMetadata>,
+    ) -> ItemHash {
+        self.list_item(item, min_bid.0, metadata, 1)
     }
 
-    /// return money to the user
+    /// withdraw caller's item from an auction
     ///
     /// # Arguments
     ///
-    /// * `account_id` - user's account id
-    /// * `amount` - amount of money that has to be returned
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
     ///
     /// # Panics
     ///
-    /// * user must have bids with equal or more amount of money that wants to return
-    fn return_money(&mut self, account_id: &AccountId, amount: &u128) {
-        assert!(
-            self.users_bids.get(&account_id).is_some(),
-            "there is no bid for user {}",
-            account_id
-        );
+    ///  * auction must be opened
+    ///  * caller must be a supplier of the item
+    ///  * nobody has made a bid for the item
+    ///  * contract must not be paused
+    pub fn remove_item(&mut self, item_hash: &ItemHash) {
+        self.assert_not_paused();
+        if !self.auction_is_open {
+            AuctionError::AuctionClosed.panic()
+        }
 
-        assert!(
-            self.users_bids.get(&account_id).unwrap() >= *amount,
-            "Can not return {} tokens for user {} because his bid is less",
-            account_id,
-            amount,
-        );
+        let mut supplier = self.get_caller_supplier();
+        let item = supplier.remove_item(item_hash);
+        self.suppliers.insert(&supplier.id, &supplier);
+        self.lots.remove(item_hash);
+        self.return_bond(&supplier.id, item.bond);
 
-        Promise::new(account_id.clone()).transfer(*amount);
+        AuctionEvent::ItemRemoved {
+            supplier: supplier.id,
+            item_hash: item_hash.clone(),
+        }
+        .emit();
     }
 
-    /// execute an auction process
+    /// change min bid and/or metadata of caller's item
+    ///
+    /// # Arguments
+    ///
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `min_bid` - new minimal bid for this item. Will be replaced to 1 if 0
+    /// * `metadata` - new title, description, media, category, condition and extra JSON of the item
     ///
     /// # Panics
     ///
-    /// * auction must not be finished
-    pub fn produce_auction(&mut self) {
-        assert!(self.auction_is_open, "Auction has already been finished");
-
-        self.auction_is_open = false;
+    ///  * auction must be opened
+    ///  * caller must be a supplier of the item
+    ///  * nobody has made a bid for the item
+    ///  * metadata must fit size limits (see `ItemMetadata::assert_valid`)
+    ///  * contract must not be paused
+    pub fn update_item(
+        &mut self,
+        item_hash: &ItemHash,
+        min_bid: Option<U128>,
+        metadata: Option<ItemMetadata>,
+    ) {
+        self.assert_not_paused();
+        if !self.auction_is_open {
+            AuctionError::AuctionClosed.panic()
+        }
 
-        let mut winners = HashMap::<ItemHash, Bid>::new();
+        let converted_min_bid = min_bid.map(|min_bid| min_bid.0);
 
-        /* #region pick winners and recalculate their sum of bids */
+        let mut supplier = self.get_caller_supplier();
+        supplier.update_item(item_hash, converted_min_bid, metadata);
+        self.suppliers.insert(&supplier.id, &supplier);
 
-        for item_and_bid in self.items_and_bids.iter() {
-            winners.insert(item_and_bid.0.clone(), item_and_bid.1.clone());
+        AuctionEvent::ItemUpdated {
+            supplier: supplier.id,
+            item_hash: item_hash.clone(),
+        }
+        .emit();
+    }
 
-            let rest_money = self
-                .users_bids
-                .get(&item_and_bid.1.account_id)
-                .unwrap_or(item_and_bid.1.bid)
-                - item_and_bid.1.bid; // if user won in an auction and loosed in another auction then we have to return money that he spent in the second auction
+    pub fn get_auction_state(&self) -> bool {
+        self.auction_is_open
+    }
 
-            self.users_bids
-                .insert(&item_and_bid.1.account_id, &rest_money);
+    /// clear all data except won items after an auction has been finished. The access policy
+    /// becomes open again. Bids are dropped without unlocking, so only `produce_auction` and
+    /// `cancel_auction` call it after they have settled every bid
+    fn clear_data(&mut self) {
+        for mut supplier in self.suppliers.values().collect::<Vec<Supplier>>() {
+            supplier.clear_items(usize::MAX);
         }
+        self.suppliers.clear();
+        self.lots.clear();
+        self.items_and_bids.clear();
+        self.bundle_bids.clear();
+        self.unit_bids.clear();
+        self.procurements.clear();
+        self.reset_access_policy();
+        self.forfeited_bonds = 0;
+        self.candle_ending = None;
+    }
+
+    /// produce exchange. send money to a supplier and item to a buyer
+    ///
+    /// # Arguments
+    /// * `item` - id of the lot (see `supplier::get_lot_id`)
+    /// * `clearing` - buyers of the item and the price of one unit that each of them pays
+    fn produce_exchange(&mut self, item: &ItemHash, clearing: &Clearing) {
+        let seller = self
+            .suppliers
+            .iter()
+            .find(|(_, supplier)| supplier.contains_item(item));
+
+        if let Some((supplier_id, mut supplier)) = seller {
+            let sold_item = supplier.sell_item(item).unwrap();
+            self.suppliers.insert(&supplier_id, &supplier);
+            self.lots.remove(item);
+
+            for (buyer, quantity) in clearing.fills.iter() {
+                let price = clearing.unit_price * *quantity as Money;
+                self.spend_locked(buyer, price);
+
+                self.record_purchase(
+                    buyer,
+                    Purchase {
+                        item_hash: item.clone(),
+                        item: sold_item.itself.clone(),
+                        supplier: supplier_id.clone(),
+                        price,
+                        quantity: *quantity,
+                        round: self.round,
+                        purchased_at: env::block_timestamp(),
+                    },
+                );
+
+                self.record_sale(
+                    &supplier_id,
+                    Sale {
+                        item_hash: item.clone(),
+                        item: sold_item.itself.clone(),
+                        buyer: buyer.clone(),
+                        price,
+                        quantity: *quantity,
+                        round: self.round,
+                        sold_at: env::block_timestamp(),
+                    },
+                );
+            }
 
-        /* #endregion pick winners and recalculate their sum of bids */
+            // send money and the listing bond to a supplier for the sold item
+            self.bonds_total -= sold_item.bond;
+            Promise::new(supplier_id).transfer(clearing.total() + sold_item.bond);
 
-        for winner in winners.iter() {
-            self.produce_exchange(&winner.1.account_id, &winner.0);
+            return;
         }
 
-        for user_bid in self.users_bids.iter() {
-            Promise::new(user_bid.0.clone()).transfer(user_bid.1.clone());
+        // suppliers don't contain item if we got here
+        for (buyer, quantity) in clearing.fills.iter() {
+            self.unlock(buyer, clearing.unit_price * *quantity as Money);
         }
-
-        self.clear_data();
     }
 
-    /// add item to an auction as a supplier
+    /// add item with the given amount of units to an auction as a caller's lot
     ///
     /// # Arguments
     ///
     /// * `item` - representation of an item
-    /// * `min_bid` - minimal bid for this item. Will be replaced to 1 if 0
+    /// * `min_bid` - minimal bid for the lot or for one unit of a multi-unit lot
+    /// * `metadata` - optional description of the item
+    /// * `quantity` - amount of units. 1 for single items
     ///
     /// # Panics
-    ///  * auction must be opened
-    pub fn add_item_to_auction(&mut self, item: &Item, min_bid: &String) {
-        let converted_min_bid = min_bid.parse::<u128>().unwrap();
-
-        assert!(self.auction_is_open, "Auction is closed. Try again later");
-
-        match self.suppliers.get(&env::predecessor_account_id()) {
-            Some(mut supplier) => supplier.add_item_to_auction(&item, &converted_min_bid),
-            None => {
-                let mut supplier = Supplier::new(&mut self.helper);
-                supplier.add_item_to_auction(&item, &converted_min_bid);
-                self.suppliers
-                    .insert(&env::predecessor_account_id(), &supplier);
+    ///
+    /// * see `add_item_to_auction`
+    pub(crate) fn list_item(
+        &mut self,
+        item: &Item,
+        min_bid: Money,
+        metadata: Option<ItemMetadata>,
+        quantity: u64,
+    ) -> ItemHash {
+        self.assert_not_paused();
+        self.assert_not_banned(&env::predecessor_account_id());
+        self.assert_allowed(&env::predecessor_account_id(), true);
+
+        if !self.auction_is_open {
+            AuctionError::AuctionClosed.panic()
+        }
+
+        let nonce = self.next_lot_nonce;
+        self.next_lot_nonce += 1;
+
+        let mut supplier = self
+            .suppliers
+            .get(&env::predecessor_account_id())
+            .unwrap_or_else(|| Supplier::new(self.round));
+
+        if env::attached_deposit() != self.listing_bond {
+            AuctionError::InvalidListingBond {
+                bond: U128(self.listing_bond),
+                attached: U128(env::attached_deposit()),
             }
+            .panic()
         }
-    }
 
-    pub fn get_auction_state(&self) -> bool {
-        self.auction_is_open
+        let item_hash = supplier.add_item_to_auction(
+            item,
+            &min_bid,
+            metadata,
+            self.round,
+            nonce,
+            self.listing_bond,
+        );
+        supplier.set_quantity(&item_hash, quantity);
+
+        // supplier keeps lengths of its collections inside itself so it has to be saved after each change
+        self.suppliers
+            .insert(&env::predecessor_account_id(), &supplier);
+        self.lots
+            .insert(&item_hash, &env::predecessor_account_id());
+        self.bonds_total += self.listing_bond;
+
+        AuctionEvent::ItemAdded {
+            supplier: env::predecessor_account_id(),
+            item_hash: item_hash.clone(),
+            round: self.round,
+        }
+        .emit();
+
+        item_hash
     }
 
-    /// produce exchange. send money to a supplier and item to a buyer
+    /// return item of the current auction's lot
     ///
     /// # Arguments
-    /// * `winner` - account id that won this item
-    /// * `item` - hash calculated from an item through the SHA256 algorithm
-    fn produce_exchange(&mut self, winner: &AccountId, item: &ItemHash) {
-        for mut supplier in self.suppliers.iter() {
-            match supplier.1.sell_item(&item) {
-                Some(sold_item) => {
-                    /* #region add won item to a winner */
-                    match self.winners_items.get(&winner) {
-                        Some(mut items) => {
-                            items.push(&sold_item.itself);
-                            self.winners_items.insert(&winner, &items);
-                        }
-
-                        None => {
-                            let mut v: Vector<Item> =
-                                Vector::new(self.helper.generate_collection_id());
-                            v.push(&sold_item.itself);
-
-                            self.winners_items.insert(&winner, &v);
-                        }
-                    }
-                    /* #endregion add won item to a winner */
-
-                    // send money to a supplier for the sold item
-                    Promise::new(supplier.0.clone()).transfer(
-                        self.items_and_bids
-                            .get(&item)
-                            .unwrap_or(Bid::new(&env::predecessor_account_id(), &0))
-                            .bid,
-                    );
-
-                    return;
+    ///
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    pub(crate) fn get_lot_item(&self, item_hash: &ItemHash) -> Option<supplier::Item> {
+        self.suppliers
+            .get(&self.lots.get(item_hash)?)?
+            .items
+            .get(item_hash)
+    }
+
+    /// return supplier that calls the contract
+    ///
+    /// # Panics
+    ///
+    /// * caller has not added any item to the auction
+    fn get_caller_supplier(&self) -> Supplier {
+        self.suppliers
+            .get(&env::predecessor_account_id())
+            .unwrap_or_else(|| {
+                AuctionError::NotSupplier {
+                    account_id: env::predecessor_account_id(),
                 }
+                .panic()
+            })
+    }
 
-                _ => {}
-            };
+    /// check that the account can make bids at the moment
+    ///
+    /// # Panics
+    ///
+    ///  * contract is paused
+    ///  * bidder is banned or not allowed by the access policy
+    ///  * auction is closed
+    ///  * ending period of the candle auction is over
+    fn assert_can_bid(&self, bidder: &AccountId) {
+        if let Err(error) = self.check_can_bid(bidder) {
+            error.panic()
         }
-
-        // suppliers don't contain item if we got here
-        self.return_money(
-            &winner,
-            &self
-                .items_and_bids
-                .get(&item)
-                .unwrap_or(Bid::new(&env::predecessor_account_id(), &0))
-                .bid,
-        );
     }
 
-    /// chech if supplier make item for his own bid
+    /// validate the bid, lock it in bidder's balance and unlock the previous one
     ///
     /// # Arguments
-    /// * `item_hash` - hash calculated from an item through the SHA256 algorithm
-    fn does_supplier_make_bid_for_his_item(&self, item_hash: &ItemHash) -> bool {
-        match self.suppliers.get(&env::predecessor_account_id()) {
-            None => false,
-            Some(supplier) => supplier.contains_item(&item_hash),
+    ///
+    /// * `bidder` - account that makes the bid
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `amount` - bid
+    fn place_bid(&mut self, bidder: &AccountId, item_hash: &ItemHash, amount: Money) {
+        // validation is shared with `check_bid` so both of them follow the same rules
+        if let Err(error) = self.check_place_bid(bidder, item_hash, amount) {
+            error.panic()
+        }
+
+        let supplier_id = self.lots.get(item_hash).unwrap();
+        let mut supplier = self.suppliers.get(&supplier_id).unwrap();
+        supplier.register_bid(item_hash);
+
+        let exists_bid = self.items_and_bids.get(item_hash);
+
+        // the bidder raises his own bid so only the difference is locked
+        self.lock(bidder, amount - self.locked_for_lot(bidder, item_hash));
+
+        // outbid money of candle auctions stays locked because an outbid bid can still win
+        if let Some(exists_bid) = exists_bid {
+            if exists_bid.account_id != *bidder && self.candle_ending.is_none() {
+                self.unlock(&exists_bid.account_id, exists_bid.bid);
+            }
         }
+
+        self.items_and_bids
+            .insert(item_hash, &Bid::new(bidder, &amount));
+
+        self.record_bid(bidder, item_hash, amount);
     }
 
     // FOR TEST PURPOSES
     #[private]
-    pub fn add_test_item(&mut self) {
-        self.add_item_to_auction(&String::from("test_item"), &"0".to_string())
+    pub fn add_test_item(&mut self) -> ItemHash {
+        self.add_item_to_auction(&String::from("test_item"), U128(0), None)
     }
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    fn get_acc_id() -> AccountId {
-        AccountId::try_from("bob.near".to_string()).unwrap()
+    use crate::history::BidStatus;
+    use crate::moderation::ModerationAction;
+    use crate::test_utils::*;
+    use near_sdk::json_types::Base64VecU8;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn get_purchase() -> Purchase {
+        Purchase {
+            item_hash: "hash".to_string(),
+            item: "test_item".to_string(),
+            supplier: account("alice.near"),
+            price: 10,
+            quantity: 1,
+            round: 1,
+            purchased_at: 0,
+        }
     }
 
     #[test]
-    #[should_panic]
+    #[should_panic(expected = "auction_already_opened: Auction is already opened")]
     fn test_start_started_auction() {
-        let mut exchange = Auction::new();
-        exchange.start_new_auction();
+        let mut exchange = setup_auction();
         exchange.start_new_auction();
     }
 
     #[test]
     fn test_get_items() {
-        let mut exchange = Auction::new();
+        let mut exchange = TestAuction::new();
 
-        let item = "test_item".to_string();
-        let mut items = Vector::<String>::new(b"i");
-        items.push(&item);
+        let mut items = Vector::<Purchase>::new(b"i");
+        items.push(&get_purchase());
 
         exchange.winners_items.insert(&get_acc_id(), &items);
 
@@ -386,54 +804,59 @@ mod tests {
 
     #[test]
     fn test_clear_data() {
-        let mut exchange = Auction::new();
+        let mut exchange = TestAuction::new();
 
-        let mut items = Vector::<String>::new(b"i");
-        items.push(&"item".to_string());
+        let mut items = Vector::<Purchase>::new(b"i");
+        items.push(&get_purchase());
 
-        exchange
-            .suppliers
-            .insert(&&get_acc_id(), &Supplier::new(&mut Helper::new()));
+        exchange.suppliers.insert(&get_acc_id(), &Supplier::new(1));
         exchange
             .items_and_bids
             .insert(&"test_key".to_string(), &Bid::new(&get_acc_id(), &10u128));
-        exchange.users_bids.insert(&get_acc_id(), &10u128);
+        exchange.escrow.insert(
+            &get_acc_id(),
+            &Escrow {
+                free: 10,
+                locked: 0,
+            },
+        );
         exchange.winners_items.insert(&get_acc_id(), &items);
+        exchange.forfeited_bonds = 10;
 
         exchange.clear_data();
 
         assert_eq!(exchange.suppliers.len(), 0);
         assert_eq!(exchange.items_and_bids.len(), 0);
-        assert_eq!(exchange.users_bids.len(), 0);
+        assert_eq!(exchange.forfeited_bonds, 0);
+        assert!(exchange.candle_ending.is_none());
 
         assert!(exchange.winners_items.contains_key(&get_acc_id()));
+        assert!(exchange.escrow.contains_key(&get_acc_id()));
     }
 
     #[test]
-    #[should_panic]
+    #[should_panic(expected = "item_not_found: Item with hash hash does not exist")]
     fn test_make_same_bids() {
-        let mut exchange = Auction::new();
-        exchange.start_new_auction();
+        let mut exchange = setup_auction();
 
         let hash = "hash".to_string();
 
-        exchange.make_bid(&hash);
-        exchange.make_bid(&hash);
+        exchange.make_bid(&hash, U128(10));
+        exchange.make_bid(&hash, U128(10));
     }
 
     #[test]
-    #[should_panic]
+    #[should_panic(expected = "auction_closed: Auction is closed. Try again later")]
     fn test_add_tem_to_closed_auction() {
-        let mut exchange = Auction::new();
-        exchange.add_item_to_auction(&"test_item".to_string(), &"10".to_string());
+        let mut exchange = TestAuction::new();
+        exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
     }
 
     #[test]
     fn test_add_item_to_auction() {
-        let mut exchange = Auction::new();
-        exchange.start_new_auction();
+        let mut exchange = setup_auction();
 
-        exchange.add_item_to_auction(&"test_item".to_string(), &"10".to_string());
+        exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
 
         assert_eq!(
             exchange.suppliers.len(),
@@ -445,17 +868,1247 @@ mod tests {
 
     #[test]
     fn test_supplier_can_not_bid_for_his_items() {
-        let mut exchange = Auction::new();
+        let (exchange, item_hash) = setup_auction_with_item(10);
+
+        assert_eq!(
+            exchange.check_bid(get_acc_id(), item_hash, U128(10)).error,
+            Some(AuctionError::OwnItem),
+            "supplier is able to bid for his item"
+        );
+    }
+
+    #[test]
+    fn test_same_item_from_different_suppliers() {
+        let mut exchange = setup_auction();
+
+        call_contract("bob.near", 0);
+        let bob_lot = exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+
+        call_contract("alice.near", 0);
+        let alice_lot = exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+
+        assert_ne!(bob_lot, alice_lot, "lots of different suppliers collide");
+        assert_eq!(exchange.suppliers.len(), 2);
+    }
+
+    #[test]
+    fn test_make_bid() {
+        let (mut exchange, item_hash) = setup_auction_with_item(10);
+
+        call_contract("alice.near", 10);
+        exchange.make_bid(&item_hash, U128(10));
+
+        assert_eq!(exchange.items_and_bids.get(&item_hash).unwrap().bid, 10);
+    }
+
+    #[test]
+    fn test_lot_contains_metadata() {
+        let mut exchange = setup_auction();
+
+        let metadata = ItemMetadata {
+            title: "Test item".to_string(),
+            description: Some("Item for tests".to_string()),
+            media: None,
+            media_hash: None,
+            category: Some("tests".to_string()),
+            condition: Some(metadata::ItemCondition::Used),
+            extra: None,
+        };
+
+        let item_hash =
+            exchange.add_item_to_auction(&"test_item".to_string(), U128(10), Some(metadata));
+
+        call_contract("alice.near", 10);
+        exchange.make_bid(&item_hash, U128(10));
+
+        let lots = exchange.get_lots(None, None, None);
+        let metadata = lots[0].metadata.as_ref().unwrap();
+
+        assert_eq!(metadata.title, "Test item");
+        assert_eq!(metadata.condition, Some(metadata::ItemCondition::Used));
+    }
+
+    #[test]
+    #[should_panic(expected = "Item title can not be empty")]
+    fn test_add_item_with_invalid_metadata() {
+        let mut exchange = setup_auction();
+
+        let metadata = ItemMetadata {
+            title: "".to_string(),
+            description: None,
+            media: None,
+            media_hash: None,
+            category: None,
+            condition: None,
+            extra: None,
+        };
+
+        exchange.add_item_to_auction(&"test_item".to_string(), U128(10), Some(metadata));
+    }
+
+    #[test]
+    fn test_remove_item() {
+        let (mut exchange, item_hash) = setup_auction_with_item(10);
+        exchange.remove_item(&item_hash);
+
+        assert_eq!(exchange.get_lots(None, None, None).len(), 0, "item has not been removed");
+
+        assert!(get_logs()
+            .last()
+            .unwrap()
+            .contains(r#""event":"item_removed""#));
+    }
+
+    #[test]
+    #[should_panic(expected = "already has bids")]
+    fn test_update_item_after_bid() {
+        let mut exchange = setup_auction();
+
+        call_contract("bob.near", 0);
+        let item_hash = exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+
+        call_contract("alice.near", 10);
+        exchange.make_bid(&item_hash, U128(10));
+
+        call_contract("bob.near", 0);
+        exchange.update_item(&item_hash, Some(U128(20)), None);
+    }
+
+    #[test]
+    fn test_add_several_items_by_one_supplier() {
+        let mut exchange = setup_auction();
+
+        exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+        let item_hash =
+            exchange.add_item_to_auction(&"another_test_item".to_string(), U128(10), None);
+        exchange.remove_item(&item_hash);
+        exchange.add_item_to_auction(&"third_test_item".to_string(), U128(10), None);
+
+        assert_eq!(
+            exchange.get_caller_supplier().items.len(),
+            2,
+            "invalid number of items"
+        );
+    }
+
+    #[test]
+    fn test_get_lots_without_bids() {
+        let (exchange, item_hash) = setup_auction_with_item(10);
+
+        let lots = exchange.get_lots(None, None, None);
+
+        assert_eq!(lots.len(), 1);
+        assert_eq!(lots[0].item_hash, item_hash);
+        assert_eq!(lots[0].winner, None);
+        assert_eq!(lots[0].current_bid, U128(10));
+        assert_eq!(
+            serde_json::to_value(&lots[0]).unwrap()["current_bid"],
+            "10",
+            "money must be serialized as a string"
+        );
+    }
+
+    #[test]
+    fn test_get_lots_page() {
+        let mut exchange = setup_auction();
+
+        let items = ["item_1", "item_2", "item_3"];
+        for item in items {
+            exchange.add_item_to_auction(&item.to_string(), U128(10), None);
+        }
+
+        let page = exchange.get_lots(Some(U64(1)), Some(U64(1)), None);
+
+        assert_eq!(exchange.get_lots_count(), U64(3));
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].item, "item_2");
+    }
+
+    #[test]
+    fn test_get_lots_with_filter() {
+        let mut exchange = setup_auction();
+
+        exchange.add_item_to_auction(&"cheap_item".to_string(), U128(10), None);
+        let item_hash = exchange.add_item_to_auction(&"expensive_item".to_string(), U128(100), None);
+
+        let filter = LotFilter {
+            min_price: Some(U128(50)),
+            ..Default::default()
+        };
+        let lots = exchange.get_lots(None, None, Some(filter));
+
+        assert_eq!(lots.len(), 1);
+        assert_eq!(lots[0].item_hash, item_hash);
+        assert_eq!(exchange.get_lot(item_hash).unwrap().item, "expensive_item");
+        assert!(exchange.get_lot("unknown".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_bid_history() {
+        let (mut exchange, item_hash) = setup_auction_with_item(10);
+
+        let alice = account("alice.near");
+        let carol = account("carol.near");
+
+        for (bidder, deposit) in [(&alice, 10), (&carol, 20), (&alice, 30)] {
+            call_contract(bidder.as_str(), deposit);
+            exchange.make_bid(&item_hash, U128(deposit));
+        }
+
+        let statuses: Vec<BidStatus> = exchange
+            .get_bid_history(item_hash.clone(), None, None)
+            .iter()
+            .map(|bid| bid.status)
+            .collect();
+
+        assert_eq!(
+            statuses,
+            vec![BidStatus::Outbid, BidStatus::Outbid, BidStatus::Leading]
+        );
+
+        call_contract("bob.near", 0);
+        exchange.produce_auction();
+
+        let alice_bids = exchange.get_bids_by_account(alice, None, None);
+        assert_eq!(alice_bids.len(), 2);
+        assert_eq!(alice_bids[0].status, BidStatus::Refunded);
+        assert_eq!(alice_bids[1].status, BidStatus::Won);
+        assert_eq!(alice_bids[1].amount, U128(30));
+
+        let carol_bids = exchange.get_bids_by_account(carol, Some(U64(0)), Some(U64(1)));
+        assert_eq!(carol_bids.len(), 1);
+        assert_eq!(carol_bids[0].status, BidStatus::Refunded);
+    }
+
+    #[test]
+    fn test_get_supplier() {
+        let mut exchange = setup_auction();
+
+        call_contract("bob.near", 0);
+        let sold_item = exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+        exchange.add_item_to_auction(&"another_test_item".to_string(), U128(10), None);
+
+        call_contract("alice.near", 15);
+        exchange.make_bid(&sold_item, U128(15));
+
+        let supplier = exchange.get_supplier(get_acc_id(), None, None, None, None);
+        assert_eq!(supplier.lots.len(), 2);
+        assert_eq!(supplier.lots_count, U64(2));
+        assert_eq!(supplier.pending_proceeds, U128(15));
+        assert_eq!(supplier.paid_proceeds, U128(0));
+
+        // proceeds are counted for all lots, not only for the requested page
+        let supplier = exchange.get_supplier(get_acc_id(), None, None, Some(U64(1)), Some(U64(1)));
+        assert_eq!(supplier.lots.len(), 1);
+        assert_eq!(supplier.lots_count, U64(2));
+        assert_eq!(supplier.pending_proceeds, U128(15));
+
+        call_contract("bob.near", 0);
+        exchange.produce_auction();
+
+        let supplier = exchange.get_supplier(get_acc_id(), None, None, None, None);
+        assert_eq!(supplier.lots.len(), 0);
+        assert_eq!(supplier.sales.len(), 1);
+        assert_eq!(supplier.sales[0].item_hash, sold_item);
+        assert_eq!(supplier.sales[0].price, U128(15));
+        assert_eq!(supplier.sales[0].round, U64(1));
+        assert_eq!(supplier.pending_proceeds, U128(0));
+        assert_eq!(supplier.paid_proceeds, U128(15));
+    }
+
+    #[test]
+    fn test_get_purchases() {
+        let mut exchange = setup_auction();
+
+        call_contract("bob.near", 0);
+        let item_hash = exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+
+        let alice = account("alice.near");
+        call_at(alice.as_str(), 15, 42);
+        exchange.make_bid(&item_hash, U128(15));
+        call_contract("bob.near", 0);
+        exchange.produce_auction();
+
+        let purchases = exchange.get_purchases(alice.clone(), None, None);
+
+        assert_eq!(purchases.len(), 1);
+        assert_eq!(purchases[0].item_hash, item_hash);
+        assert_eq!(purchases[0].item, "test_item");
+        assert_eq!(purchases[0].supplier, get_acc_id());
+        assert_eq!(purchases[0].price, U128(15));
+        assert_eq!(purchases[0].round, U64(1));
+        assert_eq!(purchases[0].purchased_at, U64(42));
+        assert_eq!(exchange.get_items(alice.to_string()), vec!["test_item"]);
+    }
+
+    #[test]
+    fn test_round_archive() {
+        let mut exchange = TestAuction::new();
+
+        call_at("bob.near", 0, 100);
         exchange.start_new_auction();
+        open_balances(&mut exchange);
+        let sold_item = exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+        let unsold_item =
+            exchange.add_item_to_auction(&"another_test_item".to_string(), U128(10), None);
+
+        let alice = account("alice.near");
+        let carol = account("carol.near");
+
+        for (bidder, deposit) in [(&carol, 10), (&alice, 20)] {
+            call_at(bidder.as_str(), deposit, 200);
+            exchange.make_bid(&sold_item, U128(deposit));
+        }
+
+        call_contract("bob.near", 0);
+        exchange.produce_auction();
+
+        let round = exchange.get_round(U64(1)).unwrap();
+
+        assert_eq!(round.id, U64(1));
+        assert_eq!(round.status, RoundStatus::Completed);
+        assert_eq!(round.started_at, U64(100));
+        assert_eq!(round.ended_at, U64(200));
+        assert_eq!(round.lots.len(), 2);
+
+        let sold_lot = round.lots.iter().find(|lot| lot.item_hash == sold_item).unwrap();
+        assert_eq!(sold_lot.winner, Some(alice));
+        assert_eq!(sold_lot.clearing_price, U128(20));
+
+        let unsold_lot = round.lots.iter().find(|lot| lot.item_hash == unsold_item).unwrap();
+        assert_eq!(unsold_lot.winner, None);
+
+        assert!(round.refunds.is_empty(), "outbid money has to be unlocked, not refunded");
+        assert_eq!(exchange.get_balance(carol).free, U128(10));
+
+        assert!(exchange.get_round(U64(0)).is_none());
+        assert!(exchange.get_round(U64(2)).is_none());
+        assert_eq!(exchange.get_rounds(None, None).len(), 1);
+    }
+
+    #[test]
+    fn test_cancel_auction() {
+        let mut exchange = setup_auction();
+
+        call_contract("dave.near", 0);
+        let item_1 = exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+        let item_2 =
+            exchange.add_item_to_auction(&"another_test_item".to_string(), U128(10), None);
+
+        let alice = account("alice.near");
+        let carol = account("carol.near");
+
+        for (bidder, item_hash, deposit, amount) in [
+            (&carol, &item_1, 10, 10),
+            (&alice, &item_1, 20, 20),
+            (&carol, &item_2, 5, 15), // outbid money is reused
+        ] {
+            call_contract(bidder.as_str(), deposit);
+            exchange.make_bid(item_hash, U128(amount));
+        }
+
+        call_contract("bob.near", 0);
+
+        assert!(!exchange.cancel_auction("fraud".to_string(), Some(U64(1))));
+        assert!(exchange.is_being_cancelled());
+        assert!(!exchange.get_auction_state());
 
-        let (_, item_hash) = supplier::Item::new(&"test_item".to_string(), &12u128);
+        // bids for the second lot, then both lots, their items and their supplier are processed
+        // one by one
+        for _ in 0..5 {
+            assert!(!exchange.cancel_auction("ignored".to_string(), Some(U64(1))));
+        }
+        assert_eq!(exchange.get_lots_count(), U64(0));
 
-        exchange.add_item_to_auction(&"test_item".to_string(), &"10".to_string());
+        assert!(exchange.cancel_auction("ignored".to_string(), Some(U64(1))));
+        assert!(!exchange.is_being_cancelled());
 
+        let round = exchange.get_round(U64(1)).unwrap();
         assert_eq!(
-            exchange.does_supplier_make_bid_for_his_item(&item_hash),
-            true,
-            "supplier is able to bid for his item"
+            round.status,
+            RoundStatus::Cancelled {
+                reason: "fraud".to_string()
+            }
         );
+        assert_eq!(round.lots.len(), 2);
+        assert!(round.lots.iter().all(|lot| lot.winner.is_none()));
+        assert_eq!(round.refunds.len(), 2);
+
+        let refunded: Money = round.refunds.iter().map(|refund| refund.amount.0).sum();
+        assert_eq!(refunded, 35);
+
+        assert_eq!(exchange.get_balance(alice.clone()).free, U128(20));
+        assert_eq!(exchange.get_balance(carol.clone()).free, U128(15));
+
+        for bidder in [alice, carol] {
+            let bids = exchange.get_bids_by_account(bidder.clone(), None, None);
+            assert!(bids.iter().all(|bid| bid.status == BidStatus::Refunded));
+            assert_eq!(exchange.get_balance(bidder).locked, U128(0));
+        }
+
+        assert_eq!(exchange.get_lots_count(), U64(0));
+        assert!(get_logs()
+            .last()
+            .unwrap()
+            .contains(r#""event":"auction_cancelled","data":{"round":1,"reason":"fraud"}"#));
+
+        exchange.start_new_auction();
+    }
+
+    #[test]
+    #[should_panic(expected = "alice.near does not have any of the required roles: [Admin]")]
+    fn test_cancel_auction_by_not_owner() {
+        let mut exchange = setup_auction();
+
+        call_contract("alice.near", 0);
+        exchange.cancel_auction("fraud".to_string(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Previous auction is being cancelled")]
+    fn test_start_auction_during_cancellation() {
+        let mut exchange = setup_auction();
+        let item_1 = exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+        let item_2 =
+            exchange.add_item_to_auction(&"another_test_item".to_string(), U128(10), None);
+
+        for item_hash in [&item_1, &item_2] {
+            call_contract("alice.near", 10);
+            exchange.make_bid(item_hash, U128(10));
+        }
+
+        call_contract("bob.near", 0);
+        exchange.cancel_auction("fraud".to_string(), Some(U64(1)));
+        exchange.start_new_auction();
+    }
+
+    #[test]
+    fn test_pause_by_guardian() {
+        let mut exchange = setup_auction();
+
+        let guardian = account("guardian.near");
+        exchange.grant_role(guardian.clone(), Role::Guardian);
+
+        call_contract(guardian.as_str(), 0);
+        exchange.pause();
+
+        assert!(exchange.is_paused());
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"auction","version":"1.0.0","event":"paused","data":{"account_id":"guardian.near"}}"#
+            ]
+        );
+
+        call_contract("bob.near", 0);
+        exchange.unpause();
+        assert!(!exchange.is_paused());
+
+        exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "missing_role: guardian.near does not have any of the")]
+    fn test_unpause_by_guardian() {
+        let mut exchange = TestAuction::new();
+        exchange.grant_role(account("guardian.near"), Role::Guardian);
+
+        call_contract("guardian.near", 0);
+        exchange.pause();
+        exchange.unpause();
+    }
+
+    #[test]
+    #[should_panic(expected = "Contract is paused")]
+    fn test_bid_when_paused() {
+        let (mut exchange, item_hash) = setup_auction_with_item(10);
+        exchange.pause();
+
+        call_contract("alice.near", 10);
+        exchange.make_bid(&item_hash, U128(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "does not have any of the required roles: [Admin, Guardian]")]
+    fn test_pause_by_stranger() {
+        let mut exchange = TestAuction::new();
+
+        call_contract("alice.near", 0);
+        exchange.pause();
+    }
+
+    #[test]
+    fn test_cancel_paused_auction() {
+        let mut exchange = setup_auction();
+        exchange.pause();
+
+        assert!(exchange.cancel_auction("bug in make_bid".to_string(), None));
+    }
+
+    #[test]
+    fn test_grant_and_revoke_role() {
+        let mut exchange = TestAuction::new();
+
+        let alice = account("alice.near");
+        let carol = account("carol.near");
+
+        assert!(exchange.has_role(get_acc_id(), Role::Admin), "owner is not an admin");
+        assert!(!exchange.has_role(alice.clone(), Role::Admin));
+
+        exchange.grant_role(alice.clone(), Role::Admin);
+        exchange.grant_role(alice.clone(), Role::Moderator);
+        assert_eq!(
+            exchange.get_roles(alice.clone()),
+            vec![Role::Admin, Role::Moderator]
+        );
+
+        call_contract(alice.as_str(), 0);
+        exchange.grant_role(carol.clone(), Role::Guardian);
+        assert!(exchange.has_role(carol.clone(), Role::Guardian));
+
+        exchange.revoke_role(alice.clone(), Role::Moderator);
+        assert!(!exchange.has_role(alice.clone(), Role::Moderator));
+        assert_eq!(
+            get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"auction","version":"1.0.0","event":"role_revoked","data":{"account_id":"alice.near","role":"moderator"}}"#
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "carol.near does not have any of the required roles: [Admin]")]
+    fn test_guardian_can_not_grant_roles() {
+        let mut exchange = TestAuction::new();
+
+        let carol = account("carol.near");
+        exchange.grant_role(carol.clone(), Role::Guardian);
+
+        call_contract(carol.as_str(), 0);
+        exchange.grant_role(carol, Role::Admin);
+    }
+
+    #[test]
+    fn test_delist_item() {
+        let mut exchange = setup_auction();
+
+        let supplier = account("dave.near");
+        let moderator = account("moderator.near");
+        exchange.grant_role(moderator.clone(), Role::Moderator);
+
+        call_contract(supplier.as_str(), 0);
+        let item_hash = exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+
+        let alice = account("alice.near");
+        let carol = account("carol.near");
+
+        for (bidder, deposit) in [(&carol, 10), (&alice, 20)] {
+            call_contract(bidder.as_str(), deposit);
+            exchange.make_bid(&item_hash, U128(deposit));
+        }
+
+        call_at(moderator.as_str(), 0, 42);
+        exchange.delist_item(item_hash.clone(), "counterfeit".to_string());
+
+        assert!(exchange.get_lot(item_hash.clone()).is_none());
+        assert_eq!(exchange.get_balance(alice.clone()).free, U128(20));
+        assert_eq!(exchange.get_balance(alice.clone()).locked, U128(0));
+        assert_eq!(exchange.get_balance(carol.clone()).free, U128(10));
+        assert_eq!(
+            exchange.get_bids_by_account(alice, None, None)[0].status,
+            BidStatus::Refunded
+        );
+
+        let log = exchange.get_moderation_log(None, None);
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].moderator, moderator);
+        assert_eq!(log[0].reason, "counterfeit");
+        assert_eq!(log[0].timestamp, U64(42));
+        assert_eq!(
+            log[0].action,
+            ModerationAction::Delisted {
+                item_hash,
+                supplier
+            }
+        );
+
+        call_contract("bob.near", 0);
+        exchange.produce_auction();
+        assert_eq!(exchange.get_round(U64(1)).unwrap().lots.len(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not have any of the required roles: [Admin, Moderator]")]
+    fn test_delist_item_by_supplier() {
+        let mut exchange = setup_auction();
+
+        let supplier = account("dave.near");
+        call_contract(supplier.as_str(), 0);
+        let item_hash = exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+        exchange.delist_item(item_hash, "I changed my mind".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Account alice.near is banned")]
+    fn test_banned_account_can_not_bid() {
+        let (mut exchange, item_hash) = setup_auction_with_item(10);
+
+        let alice = account("alice.near");
+        exchange.ban_account(alice.clone(), "shill bidding".to_string());
+        assert!(exchange.is_banned(alice.clone()));
+
+        call_contract(alice.as_str(), 10);
+        exchange.make_bid(&item_hash, U128(10));
+    }
+
+    #[test]
+    fn test_unban_account() {
+        let mut exchange = setup_auction();
+
+        let alice = account("alice.near");
+        exchange.ban_account(alice.clone(), "spam".to_string());
+        exchange.unban_account(alice.clone(), "appeal".to_string());
+
+        assert!(!exchange.is_banned(alice.clone()));
+        assert_eq!(exchange.get_moderation_log(None, None).len(), 2);
+
+        call_contract(alice.as_str(), 0);
+        exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+    }
+
+    #[test]
+    fn test_allowlist_policy() {
+        let mut exchange = setup_auction();
+        exchange.set_access_policy(AccessPolicy::Allowlist, false);
+
+        let alice = account("alice.near");
+        exchange.add_to_allowlist(vec![alice.clone()]);
+
+        let item_hash = exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+
+        call_contract(alice.as_str(), 10);
+        exchange.make_bid(&item_hash, U128(10));
+
+        assert!(!exchange.is_allowed(account("carol.near")));
+
+        call_contract("bob.near", 0);
+        exchange.produce_auction();
+
+        assert_eq!(exchange.get_access_policy(), AccessPolicy::Open);
+        assert!(exchange.is_allowed(account("carol.near")));
+
+        // the allowlist of the finished auction does not apply to the next one
+        exchange.set_access_policy(AccessPolicy::Allowlist, false);
+        assert!(!exchange.is_allowed(alice));
+    }
+
+    #[test]
+    #[should_panic(expected = "Account carol.near is not allowed to take part in this auction")]
+    fn test_bid_without_allowlist() {
+        let mut exchange = setup_auction();
+        exchange.set_access_policy(AccessPolicy::Allowlist, false);
+
+        let item_hash = exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+
+        call_contract("carol.near", 10);
+        exchange.make_bid(&item_hash, U128(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "Account dave.near is not allowed to take part in this auction")]
+    fn test_restricted_suppliers() {
+        let mut exchange = setup_auction();
+        exchange.set_access_policy(AccessPolicy::Allowlist, true);
+
+        call_contract("dave.near", 0);
+        exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+    }
+
+    #[test]
+    fn test_invite_code_policy() {
+        let mut exchange = setup_auction();
+        exchange.set_access_policy(
+            AccessPolicy::InviteCode {
+                code_hash: env::sha256(b"secret").into(),
+            },
+            false,
+        );
+
+        let alice = account("alice.near");
+        call_contract(alice.as_str(), 0);
+        assert!(!exchange.is_allowed(alice.clone()));
+
+        exchange.join_auction("secret".to_string());
+        assert!(exchange.is_allowed(alice));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid invite code")]
+    fn test_wrong_invite_code() {
+        let mut exchange = setup_auction();
+        exchange.set_access_policy(
+            AccessPolicy::InviteCode {
+                code_hash: env::sha256(b"secret").into(),
+            },
+            false,
+        );
+
+        exchange.join_auction("guess".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "contract_paused: Contract is paused")]
+    fn test_join_auction_when_paused() {
+        let mut exchange = setup_auction();
+        exchange.set_access_policy(
+            AccessPolicy::InviteCode {
+                code_hash: env::sha256(b"secret").into(),
+            },
+            false,
+        );
+        exchange.pause();
+
+        call_contract("alice.near", 0);
+        exchange.join_auction("secret".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Listing bond is 5. Attached: 0")]
+    fn test_add_item_without_bond() {
+        let mut exchange = setup_auction();
+        exchange.set_listing_bond(U128(5));
+
+        exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+    }
+
+    #[test]
+    fn test_forfeit_bond_of_delisted_item() {
+        let mut exchange = setup_auction();
+        exchange.set_listing_bond(U128(5));
+        assert_eq!(exchange.get_listing_bond(), U128(5));
+
+        call_contract("dave.near", 5);
+        let fraud = exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+        exchange.add_item_to_auction(&"another_test_item".to_string(), U128(10), None);
+
+        call_contract("bob.near", 0);
+        exchange.set_listing_bond(U128(7));
+        exchange.delist_item(fraud, "fraud".to_string());
+        exchange.produce_auction();
+
+        assert_eq!(exchange.get_round(U64(1)).unwrap().fees, U128(5));
+    }
+
+    #[test]
+    fn test_raise_bid_from_balance() {
+        let (mut exchange, item_hash) = setup_auction_with_item(10);
+
+        let alice = account("alice.near");
+        deposit_exactly(&mut exchange, "alice.near", 30);
+
+        exchange.make_bid(&item_hash, U128(10));
+        exchange.make_bid(&item_hash, U128(25));
+
+        let balance = exchange.get_balance(alice.clone());
+        assert_eq!(balance.free, U128(5));
+        assert_eq!(balance.locked, U128(25));
+
+        call_contract("bob.near", 0);
+        exchange.produce_auction();
+
+        let balance = exchange.get_balance(alice.clone());
+        assert_eq!(balance.free, U128(5));
+        assert_eq!(balance.locked, U128(0));
+
+        call_contract("alice.near", 0);
+        assert_eq!(exchange.withdraw(None), U128(5));
+        assert_eq!(exchange.get_balance(alice).free, U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough free balance. Free: 10, required: 15")]
+    fn test_bid_more_than_balance() {
+        let (mut exchange, item_hash) = setup_auction_with_item(10);
+
+        call_contract("alice.near", 10);
+        exchange.make_bid(&item_hash, U128(15));
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough free balance. Free: 0, required: 10")]
+    fn test_withdraw_locked_money() {
+        let (mut exchange, item_hash) = setup_auction_with_item(10);
+
+        call_contract("alice.near", 10);
+        exchange.make_bid(&item_hash, U128(10));
+        exchange.withdraw(Some(U128(10)));
+    }
+
+    #[test]
+    fn test_withdraw_when_paused() {
+        let mut exchange = TestAuction::new();
+
+        let alice = account("alice.near");
+        deposit_exactly(&mut exchange, "alice.near", 10);
+
+        call_contract("bob.near", 0);
+        exchange.pause();
+
+        call_contract(alice.as_str(), 0);
+        assert_eq!(exchange.withdraw(Some(U128(4))), U128(4));
+        assert_eq!(exchange.get_balance(alice).free, U128(6));
+    }
+
+    #[test]
+    fn test_make_bids() {
+        let mut exchange = setup_auction();
+        let item_1 = exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+        let item_2 =
+            exchange.add_item_to_auction(&"another_test_item".to_string(), U128(10), None);
+
+        let alice = account("alice.near");
+        call_contract(alice.as_str(), 30);
+        exchange.make_bids(vec![(item_1.clone(), U128(10)), (item_2.clone(), U128(15))]);
+
+        assert_eq!(exchange.items_and_bids.get(&item_1).unwrap().bid, 10);
+        assert_eq!(exchange.items_and_bids.get(&item_2).unwrap().bid, 15);
+
+        let balance = exchange.get_balance(alice);
+        assert_eq!(balance.free, U128(5));
+        assert_eq!(balance.locked, U128(25));
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough free balance for all bids. Free: 20, required: 25")]
+    fn test_make_bids_more_than_deposit() {
+        let mut exchange = setup_auction();
+        let item_1 = exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+        let item_2 =
+            exchange.add_item_to_auction(&"another_test_item".to_string(), U128(10), None);
+
+        call_contract("alice.near", 20);
+        exchange.make_bids(vec![(item_1, U128(10)), (item_2, U128(15))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "is bid twice")]
+    fn test_make_bids_for_same_item() {
+        let (mut exchange, item_hash) = setup_auction_with_item(10);
+
+        call_contract("alice.near", 30);
+        exchange.make_bids(vec![(item_hash.clone(), U128(10)), (item_hash, U128(20))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "This item has 10 minimum bid. Actual: 5")]
+    fn test_make_bids_with_invalid_bid() {
+        let mut exchange = setup_auction();
+        let item_1 = exchange.add_item_to_auction(&"test_item".to_string(), U128(10), None);
+        let item_2 =
+            exchange.add_item_to_auction(&"another_test_item".to_string(), U128(10), None);
+
+        call_contract("alice.near", 30);
+        exchange.make_bids(vec![(item_1, U128(10)), (item_2, U128(5))]);
+    }
+
+    fn setup_bid_check() -> (TestAuction, ItemHash) {
+        let (mut exchange, item_hash) = setup_auction_with_item(10);
+
+        deposit_exactly(&mut exchange, "alice.near", 30);
+
+        (exchange, item_hash)
+    }
+
+    #[test]
+    fn test_check_bid() {
+        let (exchange, item_hash) = setup_bid_check();
+        let alice = account("alice.near");
+
+        let check = exchange.check_bid(alice, item_hash, U128(10));
+
+        assert!(check.ok);
+        assert_eq!(check.error, None);
+        assert_eq!(check.message, None);
+        assert_eq!(check.min_amount, U128(10));
+    }
+
+    #[test]
+    fn test_check_bid_does_not_change_state() {
+        let (mut exchange, item_hash) = setup_bid_check();
+        let alice = account("alice.near");
+
+        exchange.check_bid(alice.clone(), item_hash.clone(), U128(20));
+        assert_eq!(exchange.get_balance(alice).locked, U128(0));
+
+        // the checked bid can still be made
+        exchange.make_bid(&item_hash, U128(20));
+    }
+
+    #[test]
+    fn test_check_bid_smaller_than_leading() {
+        let (mut exchange, item_hash) = setup_bid_check();
+        exchange.make_bid(&item_hash, U128(15));
+
+        let carol = account("carol.near");
+        let check = exchange.check_bid(carol, item_hash, U128(15));
+
+        assert!(!check.ok);
+        assert_eq!(
+            check.error,
+            Some(AuctionError::BiggerBidExists {
+                current_bid: U128(15)
+            })
+        );
+        assert_eq!(
+            check.message,
+            Some("A bigger bid for this item already exists".to_string())
+        );
+        assert_eq!(check.min_amount, U128(16));
+    }
+
+    #[test]
+    fn test_check_bid_raise_needs_only_difference() {
+        let (mut exchange, item_hash) = setup_bid_check();
+        let alice = account("alice.near");
+        exchange.make_bid(&item_hash, U128(15));
+
+        // 15 is locked already, so 15 of the free balance cover the raise to 30
+        let check = exchange.check_bid(alice.clone(), item_hash.clone(), U128(30));
+        assert!(check.ok);
+
+        let check = exchange.check_bid(alice, item_hash, U128(31));
+        assert_eq!(
+            check.error,
+            Some(AuctionError::NotEnoughBalance {
+                free: U128(15),
+                required: U128(16)
+            })
+        );
+        assert_eq!(
+            check.message,
+            Some("Not enough free balance. Free: 15, required: 16".to_string())
+        );
+    }
+
+    #[test]
+    fn test_check_bid_below_min_bid() {
+        let (exchange, item_hash) = setup_bid_check();
+        let alice = account("alice.near");
+
+        let check = exchange.check_bid(alice, item_hash, U128(5));
+
+        assert_eq!(
+            check.error,
+            Some(AuctionError::BelowMinBid {
+                min_bid: U128(10),
+                amount: U128(5)
+            })
+        );
+        assert_eq!(check.min_amount, U128(10));
+    }
+
+    #[test]
+    fn test_check_bid_for_unknown_item() {
+        let (exchange, _) = setup_bid_check();
+        let alice = account("alice.near");
+
+        let check = exchange.check_bid(alice, "unknown".to_string(), U128(10));
+
+        assert_eq!(
+            check.error,
+            Some(AuctionError::ItemNotFound {
+                item_hash: "unknown".to_string()
+            })
+        );
+        assert_eq!(check.min_amount, U128(0));
+    }
+
+    #[test]
+    fn test_check_bid_when_paused() {
+        let (mut exchange, item_hash) = setup_bid_check();
+        let alice = account("alice.near");
+
+        call_contract("bob.near", 0);
+        exchange.pause();
+
+        let check = exchange.check_bid(alice, item_hash, U128(10));
+        assert_eq!(check.error, Some(AuctionError::ContractPaused));
+        assert_eq!(check.message, Some("Contract is paused".to_string()));
+    }
+
+    #[test]
+    fn test_check_bid_error_code() {
+        let error = AuctionError::BiggerBidExists {
+            current_bid: U128(15),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&error).unwrap(),
+            r#"{"code":"bigger_bid_exists","current_bid":"15"}"#
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "auction_finished: Auction has already been finished")]
+    fn test_produce_finished_auction() {
+        let mut exchange = setup_auction();
+        exchange.produce_auction();
+        exchange.produce_auction();
+    }
+
+    #[test]
+    #[should_panic(expected = "missing_role: alice.near does not have any of the required roles")]
+    fn test_start_auction_by_not_admin() {
+        let mut exchange = TestAuction::new();
+        call_contract("alice.near", 0);
+        exchange.start_new_auction();
+    }
+
+    #[test]
+    #[should_panic(expected = "missing_role: alice.near does not have any of the required roles")]
+    fn test_produce_auction_by_not_admin() {
+        let mut exchange = setup_auction();
+        call_contract("alice.near", 0);
+        exchange.produce_auction();
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid_invite_code_hash: Invite code hash must be 32 bytes long")]
+    fn test_short_invite_code_hash() {
+        let mut exchange = TestAuction::new();
+        let policy = AccessPolicy::InviteCode {
+            code_hash: Base64VecU8(vec![0; 16]),
+        };
+        exchange.set_access_policy(policy, false);
+    }
+
+    #[test]
+    #[should_panic(expected = "invite_codes_not_used: Auction does not use invite codes")]
+    fn test_join_open_auction() {
+        let mut exchange = TestAuction::new();
+        exchange.join_auction("code".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "already_paused: Contract is already paused")]
+    fn test_pause_paused_contract() {
+        let mut exchange = TestAuction::new();
+        exchange.pause();
+        exchange.pause();
+    }
+
+    #[test]
+    #[should_panic(expected = "not_paused: Contract is not paused")]
+    fn test_unpause_active_contract() {
+        let mut exchange = TestAuction::new();
+        exchange.unpause();
+    }
+
+    #[test]
+    #[should_panic(expected = "not_supplier: carol.near is not a supplier")]
+    fn test_remove_item_by_not_supplier() {
+        let mut exchange = setup_auction();
+
+        call_contract("carol.near", 0);
+        exchange.remove_item(&"hash".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "own_item: Supplier can not make bid for his items")]
+    fn test_bid_for_own_item() {
+        let (mut exchange, item_hash) = setup_auction_with_item(10);
+
+        call_contract("bob.near", 0);
+        exchange.make_bid(&item_hash, U128(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "bigger_bid_exists: A bigger bid for this item already exists")]
+    fn test_bid_equal_to_leading() {
+        let (mut exchange, item_hash) = setup_bid_check();
+        exchange.make_bid(&item_hash, U128(10));
+
+        call_contract("carol.near", 10);
+        exchange.make_bid(&item_hash, U128(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "empty_bids: Bids can not be empty")]
+    fn test_make_empty_bids() {
+        let mut exchange = setup_auction();
+        exchange.make_bids(vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "no_deposit: Deposit must be attached")]
+    fn test_deposit_nothing() {
+        let mut exchange = TestAuction::new();
+        exchange.deposit();
+    }
+
+    #[test]
+    fn test_first_deposit_pays_for_storage() {
+        let mut exchange = setup_funded_auction();
+
+        let storage_usage = env::storage_usage();
+        call(&mut exchange, "erin.near", NEAR, Auction::deposit);
+        let storage_cost =
+            (env::storage_usage() - storage_usage) as Money * env::storage_byte_cost();
+        let erin = account("erin.near");
+        let free = exchange.get_balance(erin.clone()).free.0;
+        assert!(storage_cost > 0);
+        assert_eq!(free, NEAR - storage_cost);
+
+        // the balance is already stored and stays stored when it is emptied
+        call(&mut exchange, "erin.near", 0, |exchange| {
+            exchange.withdraw(None)
+        });
+        call(&mut exchange, "erin.near", NEAR, Auction::deposit);
+        assert_eq!(exchange.get_balance(erin).free.0, NEAR);
+    }
+
+    #[test]
+    fn test_first_bid_pays_for_storage() {
+        let mut exchange = setup_funded_auction();
+        let item_hash = add_funded_item(&mut exchange, "test_item");
+
+        let storage_usage = env::storage_usage();
+        call(&mut exchange, "erin.near", 2 * NEAR, |exchange| {
+            exchange.make_bid(&item_hash, U128(NEAR))
+        });
+        assert!(env::storage_usage() > storage_usage);
+
+        let balance = exchange.get_balance(account("erin.near"));
+        assert_eq!(balance.locked, U128(NEAR));
+        assert!(balance.free.0 < NEAR);
+    }
+
+    #[test]
+    #[should_panic(expected = "storage_not_covered: Attached deposit does not cover storage")]
+    fn test_first_deposit_below_storage_cost() {
+        let mut exchange = TestAuction::new();
+        call_contract("dave.near", 10);
+        exchange.deposit();
+    }
+
+    #[test]
+    fn test_liabilities() {
+        let mut exchange = setup_funded_auction();
+        let item_hash = add_funded_item(&mut exchange, "test_item");
+
+        call(&mut exchange, "alice.near", 5 * NEAR, |exchange| {
+            exchange.make_bid(&item_hash, U128(2 * NEAR))
+        });
+
+        let liabilities = exchange.get_liabilities();
+        assert_eq!(liabilities.locked, U128(2 * NEAR));
+        assert_eq!(liabilities.withdrawable, U128(3 * NEAR));
+        assert_eq!(liabilities.bonds, U128(NEAR));
+        assert_eq!(liabilities.total, U128(6 * NEAR));
+        assert!(liabilities.solvent);
+    }
+
+    #[test]
+    fn test_solvent_after_single_lots() {
+        let mut exchange = setup_funded_auction();
+        let item_1 = add_funded_item(&mut exchange, "test_item");
+        let item_2 = add_funded_item(&mut exchange, "another_item");
+        let item_3 = add_funded_item(&mut exchange, "third_item");
+
+        call(&mut exchange, "bob.near", 0, |exchange| {
+            exchange.remove_item(&item_3)
+        });
+        call(&mut exchange, "alice.near", 2 * NEAR, |exchange| {
+            exchange.make_bid(&item_1, U128(2 * NEAR))
+        });
+        call(&mut exchange, "carol.near", 5 * NEAR, |exchange| {
+            exchange.make_bid(&item_1, U128(3 * NEAR))
+        });
+        call(&mut exchange, "alice.near", 4 * NEAR, Auction::deposit);
+        call(&mut exchange, "alice.near", 0, |exchange| {
+            exchange.make_bids(vec![(item_1, U128(4 * NEAR)), (item_2, U128(NEAR))])
+        });
+        call(&mut exchange, "alice.near", 0, |exchange| {
+            exchange.withdraw(Some(U128(NEAR)))
+        });
+        call(&mut exchange, "bob.near", 0, Auction::produce_auction);
+
+        for bidder in ["alice.near", "carol.near"] {
+            call(&mut exchange, bidder, 0, |exchange| exchange.withdraw(None));
+        }
+
+        assert_eq!(exchange.get_liabilities().total, U128(0));
+    }
+
+    #[test]
+    fn test_solvent_after_units_bundles_and_procurements() {
+        let mut exchange = setup_funded_auction();
+
+        let units = call(&mut exchange, "bob.near", NEAR, |exchange| {
+            exchange.add_units_to_auction(&"ticket".to_string(), U64(3), U128(NEAR), None)
+        });
+        let item_1 = add_funded_item(&mut exchange, "test_item");
+        let item_2 = add_funded_item(&mut exchange, "another_item");
+
+        call(&mut exchange, "alice.near", 4 * NEAR, |exchange| {
+            exchange.make_unit_bid(units.clone(), U64(2), U128(2 * NEAR))
+        });
+        call(&mut exchange, "carol.near", 6 * NEAR, |exchange| {
+            exchange.make_unit_bid(units, U64(2), U128(3 * NEAR))
+        });
+        call(&mut exchange, "dave.near", 3 * NEAR, |exchange| {
+            exchange.make_bundle_bid(vec![item_1.clone(), item_2], U128(3 * NEAR))
+        });
+        call(&mut exchange, "carol.near", 2 * NEAR, |exchange| {
+            exchange.make_bid(&item_1, U128(2 * NEAR))
+        });
+        let request_id = call(&mut exchange, "alice.near", 5 * NEAR, |exchange| {
+            exchange.create_procurement("100 chairs".to_string(), U128(5 * NEAR))
+        });
+        call(&mut exchange, "dave.near", STORAGE_DEPOSIT, |exchange| {
+            exchange.make_ask(request_id, U128(4 * NEAR))
+        });
+        call(&mut exchange, "bob.near", 0, Auction::produce_auction);
+
+        assert_eq!(exchange.get_liabilities().bonds, U128(0));
+    }
+
+    #[test]
+    fn test_solvent_after_delisting_and_cancellation() {
+        let mut exchange = setup_funded_auction();
+        let item_1 = add_funded_item(&mut exchange, "test_item");
+        let item_2 = add_funded_item(&mut exchange, "another_item");
+
+        call(&mut exchange, "alice.near", 2 * NEAR, |exchange| {
+            exchange.make_bid(&item_1, U128(2 * NEAR))
+        });
+        call(&mut exchange, "carol.near", 2 * NEAR, |exchange| {
+            exchange.make_bid(&item_2, U128(2 * NEAR))
+        });
+        call(&mut exchange, "bob.near", 0, |exchange| {
+            exchange.delist_item(item_1, "spam".to_string())
+        });
+
+        while !call(&mut exchange, "bob.near", 0, |exchange| {
+            exchange.cancel_auction("mistake".to_string(), Some(U64(1)))
+        }) {}
+
+        let liabilities = exchange.get_liabilities();
+        assert_eq!(liabilities.withdrawable, U128(4 * NEAR));
+        assert_eq!(liabilities.bonds, U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "insolvent: Contract is insolvent")]
+    fn test_lost_money_is_insolvent() {
+        let mut exchange = setup_funded_auction();
+
+        call_contract("alice.near", 5 * NEAR);
+        exchange.deposit();
+
+        // the contract has lost 1 NEAR that alice can withdraw
+        testing_env!(VMContextBuilder::new()
+            .account_balance(env::account_balance() - NEAR)
+            .storage_usage(env::storage_usage())
+            .build());
+        exchange.assert_solvent();
     }
 }
@@ -0,0 +1,146 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base64VecU8, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::errors::AuctionError;
+
+pub const MAX_TITLE_LENGTH: usize = 256;
+pub const MAX_DESCRIPTION_LENGTH: usize = 4096;
+pub const MAX_MEDIA_LENGTH: usize = 512;
+pub const MAX_CATEGORY_LENGTH: usize = 64;
+pub const MAX_EXTRA_LENGTH: usize = 4096;
+pub const MEDIA_HASH_LENGTH: usize = 32;
+
+/// Physical state of an item
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemCondition {
+    New,
+    LikeNew,
+    Used,
+    Refurbished,
+    ForParts,
+}
+
+/// Information about an item that a supplier shows to buyers
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ItemMetadata {
+    pub title: String,
+    pub description: Option<String>,
+    pub media: Option<String>, // URL to an image or any other media of the item
+    pub media_hash: Option<Base64VecU8>, // SHA256 of the content referenced by `media`
+    pub category: Option<String>,
+    pub condition: Option<ItemCondition>,
+    pub extra: Option<String>, // any additional data as a JSON string
+}
+
+impl ItemMetadata {
+    /// check that metadata fits size limits and is consistent
+    ///
+    /// # Panics
+    ///
+    /// * title is empty or longer than `MAX_TITLE_LENGTH` bytes
+    /// * description, media, category or extra are longer than their limits
+    /// * only one of `media` and `media_hash` is set or `media_hash` is not 32 bytes long
+    /// * extra is not a valid JSON
+    pub fn assert_valid(&self) {
+        if self.title.is_empty() {
+            AuctionError::EmptyTitle.panic()
+        }
+        assert_length("title", &self.title, MAX_TITLE_LENGTH);
+
+        if let Some(description) = &self.description {
+            assert_length("description", description, MAX_DESCRIPTION_LENGTH);
+        }
+
+        if let Some(category) = &self.category {
+            assert_length("category", category, MAX_CATEGORY_LENGTH);
+        }
+
+        if self.media.is_some() != self.media_hash.is_some() {
+            AuctionError::UnpairedMedia.panic()
+        }
+
+        if let Some(media) = &self.media {
+            assert_length("media", media, MAX_MEDIA_LENGTH);
+        }
+
+        if let Some(media_hash) = &self.media_hash {
+            if media_hash.0.len() != MEDIA_HASH_LENGTH {
+                AuctionError::InvalidMediaHash.panic()
+            }
+        }
+
+        if let Some(extra) = &self.extra {
+            assert_length("extra", extra, MAX_EXTRA_LENGTH);
+            if serde_json::from_str::<serde_json::Value>(extra).is_err() {
+                AuctionError::InvalidExtra.panic()
+            }
+        }
+    }
+}
+
+fn assert_length(field: &str, value: &str, max_length: usize) {
+    if value.len() > max_length {
+        AuctionError::FieldTooLong {
+            field: field.to_string(),
+            max_length: U64(max_length as u64),
+            length: U64(value.len() as u64),
+        }
+        .panic()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_metadata() -> ItemMetadata {
+        ItemMetadata {
+            title: "Test item".to_string(),
+            description: Some("Item for tests".to_string()),
+            media: Some("https://example.com/item.png".to_string()),
+            media_hash: Some(Base64VecU8(vec![0u8; MEDIA_HASH_LENGTH])),
+            category: Some("tests".to_string()),
+            condition: Some(ItemCondition::New),
+            extra: Some("{\"color\":\"red\"}".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_valid_metadata() {
+        get_metadata().assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Item title is too long")]
+    fn test_too_long_title() {
+        let mut metadata = get_metadata();
+        metadata.title = "a".repeat(MAX_TITLE_LENGTH + 1);
+        metadata.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Media and media hash must be provided together")]
+    fn test_media_without_hash() {
+        let mut metadata = get_metadata();
+        metadata.media_hash = None;
+        metadata.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Item extra must be a valid JSON")]
+    fn test_invalid_extra() {
+        let mut metadata = get_metadata();
+        metadata.extra = Some("{color: red".to_string());
+        metadata.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid_media_hash: Media hash must be 32 bytes long")]
+    fn test_short_media_hash() {
+        let mut metadata = get_metadata();
+        metadata.media_hash = Some(Base64VecU8(vec![0; 16]));
+        metadata.assert_valid();
+    }
+}
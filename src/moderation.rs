@@ -0,0 +1,178 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::errors::AuctionError;
+use crate::events::AuctionEvent;
+use crate::history::BidStatus;
+use crate::roles::Role;
+use crate::views::ModerationRecordView;
+use crate::{Auction, AuctionExt, ItemHash, DEFAULT_PAGE_LIMIT};
+
+/// What a moderator has done
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ModerationAction {
+    Delisted {
+        item_hash: ItemHash,
+        supplier: AccountId,
+    },
+    Banned {
+        account_id: AccountId,
+    },
+    Unbanned {
+        account_id: AccountId,
+    },
+}
+
+/// Entry of the moderation log
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct ModerationRecord {
+    pub moderator: AccountId,
+    pub action: ModerationAction,
+    pub reason: String,
+    pub timestamp: u64,
+}
+
+#[near_bindgen]
+impl Auction {
+    /// take down an offending lot. Its current bid or unit bids are unlocked in the bidders' balances.
+    /// The listing bond of the lot goes to the owner
+    ///
+    /// # Arguments
+    ///
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `reason` - why the lot has been delisted
+    ///
+    /// # Panics
+    ///
+    /// * caller must be an admin or a moderator
+    /// * auction must be opened
+    /// * lot must exist
+    /// * contract must not be paused
+    pub fn delist_item(&mut self, item_hash: ItemHash, reason: String) {
+        self.assert_role(&[Role::Admin, Role::Moderator]);
+        self.assert_not_paused();
+        if !self.auction_is_open {
+            AuctionError::AuctionClosed.panic()
+        }
+
+        let supplier_id = self.lots.get(&item_hash).unwrap_or_else(|| {
+            AuctionError::ItemNotFound {
+                item_hash: item_hash.clone(),
+            }
+            .panic()
+        });
+
+        let mut supplier = self.suppliers.get(&supplier_id).unwrap();
+        let item = supplier.delist_item(&item_hash);
+        self.suppliers.insert(&supplier_id, &supplier);
+        self.lots.remove(&item_hash);
+        self.forfeit_bond(item.bond);
+
+        self.release_lot_bids(&item_hash);
+        self.unlock_unit_bids(&item_hash);
+
+        self.settle_bid_history(&item_hash, BidStatus::Refunded);
+
+        self.log_moderation(
+            ModerationAction::Delisted {
+                item_hash: item_hash.clone(),
+                supplier: supplier_id.clone(),
+            },
+            reason.clone(),
+        );
+
+        AuctionEvent::ItemDelisted {
+            supplier: supplier_id,
+            item_hash,
+            reason,
+        }
+        .emit();
+    }
+
+    /// forbid the account to add items and make bids
+    ///
+    /// # Panics
+    ///
+    /// * caller must be an admin or a moderator
+    pub fn ban_account(&mut self, account_id: AccountId, reason: String) {
+        self.assert_role(&[Role::Admin, Role::Moderator]);
+
+        if self.blocklist.insert(&account_id) {
+            self.log_moderation(
+                ModerationAction::Banned {
+                    account_id: account_id.clone(),
+                },
+                reason.clone(),
+            );
+
+            AuctionEvent::AccountBanned { account_id, reason }.emit();
+        }
+    }
+
+    /// # Panics
+    ///
+    /// * caller must be an admin or a moderator
+    pub fn unban_account(&mut self, account_id: AccountId, reason: String) {
+        self.assert_role(&[Role::Admin, Role::Moderator]);
+
+        if self.blocklist.remove(&account_id) {
+            self.log_moderation(
+                ModerationAction::Unbanned {
+                    account_id: account_id.clone(),
+                },
+                reason,
+            );
+
+            AuctionEvent::AccountUnbanned { account_id }.emit();
+        }
+    }
+
+    pub fn is_banned(&self, account_id: AccountId) -> bool {
+        self.blocklist.contains(&account_id)
+    }
+
+    /// return actions of moderators, oldest first
+    ///
+    /// # Arguments
+    ///
+    /// * `from_index` - index of the first record. 0 if not set
+    /// * `limit` - max amount of records. `DEFAULT_PAGE_LIMIT` if not set
+    pub fn get_moderation_log(
+        &self,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<ModerationRecordView> {
+        self.moderation_log
+            .iter()
+            .skip(from_index.map_or(0, |index| index.0) as usize)
+            .take(limit.map_or(DEFAULT_PAGE_LIMIT, |limit| limit.0) as usize)
+            .map(ModerationRecordView::from)
+            .collect()
+    }
+}
+
+impl Auction {
+    /// # Panics
+    ///
+    /// * account is in the blocklist
+    pub(crate) fn assert_not_banned(&self, account_id: &AccountId) {
+        if self.blocklist.contains(account_id) {
+            AuctionError::AccountBanned {
+                account_id: account_id.clone(),
+            }
+            .panic()
+        }
+    }
+
+    fn log_moderation(&mut self, action: ModerationAction, reason: String) {
+        self.moderation_log.push(&ModerationRecord {
+            moderator: env::predecessor_account_id(),
+            action,
+            reason,
+            timestamp: env::block_timestamp(),
+        });
+    }
+}
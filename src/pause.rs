@@ -0,0 +1,65 @@
+use near_sdk::{env, near_bindgen};
+
+use crate::errors::AuctionError;
+use crate::events::AuctionEvent;
+use crate::roles::Role;
+use crate::{Auction, AuctionExt};
+
+#[near_bindgen]
+impl Auction {
+    /// freeze the contract. Nobody can start or produce auctions, add, change or remove items and
+    /// make bids until the contract is unpaused. Refunds still work
+    ///
+    /// # Panics
+    ///
+    /// * caller must be an admin or a guardian
+    /// * contract must not be paused
+    pub fn pause(&mut self) {
+        self.assert_role(&[Role::Admin, Role::Guardian]);
+        if self.paused {
+            AuctionError::AlreadyPaused.panic()
+        }
+
+        self.paused = true;
+
+        AuctionEvent::Paused {
+            account_id: env::predecessor_account_id(),
+        }
+        .emit();
+    }
+
+    /// resume the contract after `pause`
+    ///
+    /// # Panics
+    ///
+    /// * caller must be an admin. Guardians can only pause
+    /// * contract must be paused
+    pub fn unpause(&mut self) {
+        self.assert_role(&[Role::Admin]);
+        if !self.paused {
+            AuctionError::NotPaused.panic()
+        }
+
+        self.paused = false;
+
+        AuctionEvent::Unpaused {
+            account_id: env::predecessor_account_id(),
+        }
+        .emit();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+impl Auction {
+    /// # Panics
+    ///
+    /// * contract is paused
+    pub(crate) fn assert_not_paused(&self) {
+        if self.paused {
+            AuctionError::ContractPaused.panic()
+        }
+    }
+}
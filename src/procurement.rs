@@ -0,0 +1,460 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::Vector;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, near_bindgen, AccountId, Promise};
+
+use crate::errors::AuctionError;
+use crate::events::AuctionEvent;
+use crate::rounds::ProcurementOutcome;
+use crate::sales::{Purchase, Sale};
+use crate::solvency::pay_for_storage;
+use crate::supplier::get_lot_id;
+use crate::views::ProcurementView;
+use crate::{Auction, AuctionExt, ItemHash, Money, StorageKey, DEFAULT_PAGE_LIMIT};
+
+/// max length of a procurement request description in bytes
+pub const MAX_DESCRIPTION_LENGTH: usize = 1024;
+
+/// Buyer's request for an item. Sellers compete by asking less money for it
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct ProcurementRequest {
+    pub buyer: AccountId,
+    pub description: String,
+    pub budget: Money, // locked in the buyer's balance until the auction is finished
+    pub best_ask: Option<Ask>, // the smallest ask. None if no seller has made an ask yet
+    pub created_at: u64,
+}
+
+/// Seller's offer for a procurement request
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct Ask {
+    pub account_id: AccountId,
+    pub amount: Money,
+    pub created_at: u64,
+}
+
+#[near_bindgen]
+impl Auction {
+    /// request an item as a buyer. Sellers make asks for it until the auction is finished,
+    /// then the smallest ask is paid from the budget and the rest is unlocked
+    ///
+    /// # Arguments
+    ///
+    /// * `description` - what the buyer wants to buy
+    /// * `budget` - max price. Attached deposit is added to caller's free balance before the
+    /// budget is locked
+    ///
+    /// return id of the request. It is derived the same way as lot ids (see `supplier::get_lot_id`)
+    ///
+    /// # Panics
+    ///
+    ///  * description is empty or longer than `MAX_DESCRIPTION_LENGTH`
+    ///  * budget is 0
+    ///  * free balance must cover the budget
+    ///  * attached deposit must cover storage of a new balance (see `deposit`)
+    ///  * caller can not bid (see `make_bid`)
+    #[payable]
+    pub fn create_procurement(&mut self, description: String, budget: U128) -> ItemHash {
+        let buyer = env::predecessor_account_id();
+        self.assert_can_bid(&buyer);
+
+        if description.is_empty() || description.len() > MAX_DESCRIPTION_LENGTH {
+            AuctionError::InvalidDescription.panic()
+        }
+        if budget.0 == 0 {
+            AuctionError::ZeroBudget.panic()
+        }
+
+        self.deposit_attached(&buyer);
+
+        self.lock(&buyer, budget.0);
+
+        let nonce = self.next_lot_nonce;
+        self.next_lot_nonce += 1;
+
+        let request_id = get_lot_id(&buyer, self.round, nonce);
+
+        self.procurements.insert(
+            &request_id,
+            &ProcurementRequest {
+                buyer: buyer.clone(),
+                description,
+                budget: budget.0,
+                best_ask: None,
+                created_at: env::block_timestamp(),
+            },
+        );
+
+        AuctionEvent::ProcurementCreated {
+            buyer,
+            request_id: request_id.clone(),
+            budget,
+        }
+        .emit();
+
+        request_id
+    }
+
+    /// offer to sell the requested item for the amount. Each ask must be smaller than the previous one.
+    /// Every ask is kept in the history of the request, so it is paid with a storage deposit and
+    /// the rest of the deposit is returned
+    ///
+    /// # Arguments
+    ///
+    /// * `request_id` - id of the procurement request
+    /// * `amount` - money that the seller wants for the item
+    ///
+    /// # Panics
+    ///
+    ///  * auction must be opened
+    ///  * request must exist and must not belong to the caller
+    ///  * amount must be positive and must not exceed the budget
+    ///  * a smaller or equal ask must not exist
+    ///  * ending period of the candle auction must not be over
+    ///  * contract must not be paused
+    ///  * seller must not be banned
+    ///  * seller must be allowed by the access policy if it restricts suppliers
+    ///  * deposit does not cover storage of the ask
+    #[payable]
+    pub fn make_ask(&mut self, request_id: ItemHash, amount: U128) {
+        let seller = env::predecessor_account_id();
+        self.assert_not_paused();
+        self.assert_not_banned(&seller);
+        self.assert_allowed(&seller, true);
+        if !self.auction_is_open {
+            AuctionError::AuctionClosed.panic()
+        }
+        if let Some(ending) = &self.candle_ending {
+            if env::block_timestamp() > ending.ends_at {
+                AuctionError::CandleEnded.panic()
+            }
+        }
+
+        let mut request = self.procurements.get(&request_id).unwrap_or_else(|| {
+            AuctionError::ProcurementNotFound {
+                request_id: request_id.clone(),
+            }
+            .panic()
+        });
+
+        if request.buyer == seller {
+            AuctionError::OwnRequest.panic()
+        }
+        if amount.0 == 0 {
+            AuctionError::ZeroAsk.panic()
+        }
+        if amount.0 > request.budget {
+            AuctionError::AskAboveBudget {
+                budget: U128(request.budget),
+                amount,
+            }
+            .panic()
+        }
+
+        if let Some(best_ask) = &request.best_ask {
+            if amount.0 >= best_ask.amount {
+                AuctionError::SmallerAskExists {
+                    current_ask: U128(best_ask.amount),
+                }
+                .panic()
+            }
+        }
+
+        let ask = Ask {
+            account_id: seller.clone(),
+            amount: amount.0,
+            created_at: env::block_timestamp(),
+        };
+
+        let storage_usage = env::storage_usage();
+        let mut history = self.ask_history.get(&request_id).unwrap_or_else(|| {
+            Vector::new(StorageKey::AskHistory {
+                request_hash: env::sha256_array(request_id.as_bytes()),
+            })
+        });
+        history.push(&ask);
+        self.ask_history.insert(&request_id, &history);
+
+        request.best_ask = Some(ask);
+        self.procurements.insert(&request_id, &request);
+
+        let refund = pay_for_storage(storage_usage);
+        if refund > 0 {
+            Promise::new(seller).transfer(refund);
+        }
+    }
+
+    /// return procurement request of the current auction
+    ///
+    /// # Arguments
+    ///
+    /// * `request_id` - id of the procurement request
+    pub fn get_procurement(&self, request_id: ItemHash) -> Option<ProcurementView> {
+        self.procurements
+            .get(&request_id)
+            .map(|request| ProcurementView::new(request_id, request))
+    }
+
+    /// return procurement requests of the current auction
+    ///
+    /// # Arguments
+    ///
+    /// * `from_index` - index of the first request. 0 if not set
+    /// * `limit` - max amount of requests. `DEFAULT_PAGE_LIMIT` if not set
+    pub fn get_procurements(
+        &self,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<ProcurementView> {
+        self.procurements
+            .iter()
+            .skip(from_index.map_or(0, |index| index.0) as usize)
+            .take(limit.map_or(DEFAULT_PAGE_LIMIT, |limit| limit.0) as usize)
+            .map(|(request_id, request)| ProcurementView::new(request_id, request))
+            .collect()
+    }
+}
+
+impl Auction {
+    /// pay the smallest ask of each procurement request from its budget and unlock the rest of
+    /// the budget in the buyer's balance. Budgets of requests without asks are unlocked entirely
+    ///
+    /// return result of the auction for each request
+    pub(crate) fn settle_procurements(&mut self) -> Vector<ProcurementOutcome> {
+        let requests: Vec<(ItemHash, ProcurementRequest)> = self.procurements.iter().collect();
+        let mut outcomes = Vector::new(StorageKey::RoundProcurements { round: self.round });
+
+        for (request_id, request) in requests {
+            outcomes.push(&ProcurementOutcome {
+                request_id: request_id.clone(),
+                buyer: request.buyer.clone(),
+                seller: request.best_ask.as_ref().map(|ask| ask.account_id.clone()),
+                price: request.best_ask.as_ref().map_or(0, |ask| ask.amount),
+            });
+
+            let ask = match request.best_ask {
+                Some(ask) => ask,
+                None => {
+                    self.unlock(&request.buyer, request.budget);
+                    continue;
+                }
+            };
+
+            self.spend_locked(&request.buyer, ask.amount);
+            self.unlock(&request.buyer, request.budget - ask.amount);
+
+            self.record_purchase(
+                &request.buyer,
+                Purchase {
+                    item_hash: request_id.clone(),
+                    item: request.description.clone(),
+                    supplier: ask.account_id.clone(),
+                    price: ask.amount,
+                    quantity: 1,
+                    round: self.round,
+                    purchased_at: env::block_timestamp(),
+                },
+            );
+
+            self.record_sale(
+                &ask.account_id,
+                Sale {
+                    item_hash: request_id,
+                    item: request.description,
+                    buyer: request.buyer,
+                    price: ask.amount,
+                    quantity: 1,
+                    round: self.round,
+                    sold_at: env::block_timestamp(),
+                },
+            );
+
+            Promise::new(ask.account_id).transfer(ask.amount);
+        }
+
+        self.procurements.clear();
+
+        outcomes
+    }
+
+    /// make the last ask before the close time of a candle auction the best ask of each request.
+    /// Asks are only getting smaller so it is the smallest ask that counts
+    ///
+    /// # Arguments
+    ///
+    /// * `close_time` - moment when the candle auction has been closed
+    pub(crate) fn close_procurements_at(&mut self, close_time: u64) {
+        let requests: Vec<(ItemHash, ProcurementRequest)> = self.procurements.iter().collect();
+
+        for (request_id, mut request) in requests {
+            let is_late = match &request.best_ask {
+                Some(ask) => ask.created_at > close_time,
+                None => false,
+            };
+
+            if is_late {
+                request.best_ask = self.ask_history.get(&request_id).and_then(|history| {
+                    history
+                        .iter()
+                        .rev()
+                        .find(|ask| ask.created_at <= close_time)
+                });
+                self.procurements.insert(&request_id, &request);
+            }
+        }
+    }
+
+    /// remove procurement request and unlock its budget in the buyer's balance
+    ///
+    /// return buyer and unlocked budget
+    pub(crate) fn unlock_procurement(&mut self, request_id: &ItemHash) -> (AccountId, Money) {
+        let request = self.procurements.remove(request_id).unwrap();
+        self.unlock(&request.buyer, request.budget);
+
+        (request.buyer, request.budget)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn setup_procurement(asks: &[(&str, Money)]) -> (TestAuction, ItemHash) {
+        let mut exchange = setup_auction();
+
+        call_contract("alice.near", 100);
+        let request_id = exchange.create_procurement("100 chairs".to_string(), U128(100));
+
+        for (seller, amount) in asks {
+            call_contract(seller, STORAGE_DEPOSIT);
+            exchange.make_ask(request_id.clone(), U128(*amount));
+        }
+
+        (exchange, request_id)
+    }
+
+    #[test]
+    fn test_procurement_pays_smallest_ask() {
+        let (mut exchange, request_id) =
+            setup_procurement(&[("carol.near", 80), ("dave.near", 60)]);
+
+        let request = exchange.get_procurement(request_id).unwrap();
+        assert_eq!(request.best_ask, Some(U128(60)));
+        assert_eq!(request.best_seller, Some(account("dave.near")));
+
+        call_contract("bob.near", 0);
+        exchange.produce_auction();
+
+        let alice = account("alice.near");
+        let purchases = exchange.get_purchases(alice.clone(), None, None);
+        assert_eq!(purchases.len(), 1);
+        assert_eq!(purchases[0].price, U128(60));
+        assert_eq!(purchases[0].item, "100 chairs".to_string());
+        assert_eq!(exchange.get_balance(alice.clone()).free, U128(40));
+        assert_eq!(exchange.get_balance(alice).locked, U128(0));
+
+        let dave = account("dave.near");
+        let seller = exchange.get_supplier(dave.clone(), None, None, None, None);
+        assert_eq!(seller.sales.len(), 1);
+        assert_eq!(seller.paid_proceeds, U128(60));
+
+        assert!(exchange.get_procurements(None, None).is_empty());
+
+        let procurements = exchange.get_round(U64(1)).unwrap().procurements;
+        assert_eq!(procurements.len(), 1);
+        assert_eq!(procurements[0].seller, Some(dave));
+        assert_eq!(procurements[0].price, U128(60));
+    }
+
+    #[test]
+    fn test_procurement_without_asks() {
+        let (mut exchange, _) = setup_procurement(&[]);
+
+        call_contract("bob.near", 0);
+        exchange.produce_auction();
+
+        let alice = account("alice.near");
+        assert!(exchange.get_purchases(alice.clone(), None, None).is_empty());
+        assert_eq!(exchange.get_balance(alice).free, U128(100));
+    }
+
+    #[test]
+    #[should_panic(expected = "Ask must not exceed the budget 100. Actual: 101")]
+    fn test_ask_above_budget() {
+        setup_procurement(&[("carol.near", 101)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "zero_ask: Ask must be positive")]
+    fn test_zero_ask() {
+        setup_procurement(&[("carol.near", 0)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "A smaller ask for this request already exists")]
+    fn test_ask_must_decrease() {
+        setup_procurement(&[("carol.near", 60), ("dave.near", 60)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "storage_not_covered: Attached deposit does not cover storage")]
+    fn test_ask_without_storage_deposit() {
+        let (mut exchange, request_id) = setup_procurement(&[]);
+
+        call_contract("carol.near", 0);
+        exchange.make_ask(request_id, U128(60));
+    }
+
+    #[test]
+    fn test_cancel_auction_with_procurement() {
+        let (mut exchange, _) = setup_procurement(&[("carol.near", 60)]);
+
+        call_contract("bob.near", 0);
+        assert!(exchange.cancel_auction("fraud".to_string(), None));
+
+        let alice = account("alice.near");
+        assert_eq!(exchange.get_balance(alice).free, U128(100));
+
+        let round = exchange.get_round(U64(1)).unwrap();
+        assert_eq!(round.refunds.len(), 1);
+        assert_eq!(round.procurements.len(), 1);
+        assert_eq!(round.procurements[0].seller, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid_description: Description must be from 1 to 1024 bytes")]
+    fn test_procurement_without_description() {
+        let mut exchange = setup_auction();
+
+        call_contract("alice.near", 100);
+        exchange.create_procurement("".to_string(), U128(100));
+    }
+
+    #[test]
+    #[should_panic(expected = "zero_budget: Budget must be positive")]
+    fn test_procurement_without_budget() {
+        let mut exchange = setup_auction();
+
+        call_contract("alice.near", 0);
+        exchange.create_procurement("100 chairs".to_string(), U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "procurement_not_found: Procurement request hash does not exist")]
+    fn test_ask_for_unknown_request() {
+        let mut exchange = setup_auction();
+
+        call_contract("carol.near", 0);
+        exchange.make_ask("hash".to_string(), U128(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "own_request: Buyer can not make ask for his request")]
+    fn test_ask_for_own_request() {
+        let (mut exchange, request_id) = setup_procurement(&[]);
+
+        call_contract("alice.near", 0);
+        exchange.make_ask(request_id, U128(10));
+    }
+}
@@ -0,0 +1,92 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::errors::AuctionError;
+use crate::events::AuctionEvent;
+use crate::{Auction, AuctionExt};
+
+/// Permissions that can be granted to accounts besides the owner
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, Copy, PartialEq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,     // manages roles and configuration, cancels auctions
+    Moderator, // delists offending lots and bans accounts
+    Guardian,  // pauses the contract; only admins unpause it
+}
+
+#[near_bindgen]
+impl Auction {
+    /// # Panics
+    ///
+    /// * caller must be an admin
+    pub fn grant_role(&mut self, account_id: AccountId, role: Role) {
+        self.assert_role(&[Role::Admin]);
+
+        let mut roles = self.roles.get(&account_id).unwrap_or_default();
+
+        if !roles.contains(&role) {
+            roles.push(role);
+            self.roles.insert(&account_id, &roles);
+
+            AuctionEvent::RoleGranted { account_id, role }.emit();
+        }
+    }
+
+    /// # Panics
+    ///
+    /// * caller must be an admin
+    pub fn revoke_role(&mut self, account_id: AccountId, role: Role) {
+        self.assert_role(&[Role::Admin]);
+
+        let mut roles = self.roles.get(&account_id).unwrap_or_default();
+
+        if roles.contains(&role) {
+            roles.retain(|granted| *granted != role);
+
+            if roles.is_empty() {
+                self.roles.remove(&account_id);
+            } else {
+                self.roles.insert(&account_id, &roles);
+            }
+
+            AuctionEvent::RoleRevoked { account_id, role }.emit();
+        }
+    }
+
+    /// check if the account has the role. The owner is always an admin
+    pub fn has_role(&self, account_id: AccountId, role: Role) -> bool {
+        (role == Role::Admin && account_id == self.owner_id)
+            || self
+                .roles
+                .get(&account_id)
+                .is_some_and(|roles| roles.contains(&role))
+    }
+
+    /// return roles that have been granted to the account
+    pub fn get_roles(&self, account_id: AccountId) -> Vec<Role> {
+        self.roles.get(&account_id).unwrap_or_default()
+    }
+}
+
+impl Auction {
+    /// # Panics
+    ///
+    /// * caller does not have any of the roles
+    pub(crate) fn assert_role(&self, roles: &[Role]) {
+        let caller = env::predecessor_account_id();
+
+        if !roles
+            .iter()
+            .any(|role| self.has_role(caller.clone(), *role))
+        {
+            AuctionError::MissingRole {
+                account_id: caller,
+                roles: roles.to_vec(),
+            }
+            .panic()
+        }
+    }
+}
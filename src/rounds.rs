@@ -0,0 +1,156 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::Vector;
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::units::Clearing;
+use crate::views::RoundView;
+use crate::{Auction, AuctionExt, ItemHash, Money, StorageKey, DEFAULT_PAGE_LIMIT};
+
+/// Result of an auction for one lot
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct LotOutcome {
+    pub item_hash: ItemHash,
+    pub supplier: AccountId,
+    pub winner: Option<AccountId>, // None if nobody has made a bid. Buyer of the most units for multi-unit lots
+    pub clearing_price: Money,     // price of one unit for multi-unit lots
+    pub units_sold: u64,
+}
+
+impl LotOutcome {
+    /// # Arguments
+    ///
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `supplier` - owner of the lot
+    /// * `clearing` - buyers and price of the lot. None if the lot has not been sold
+    pub fn new(item_hash: ItemHash, supplier: AccountId, clearing: Option<&Clearing>) -> Self {
+        Self {
+            clearing_price: clearing.map_or(0, |clearing| clearing.unit_price),
+            winner: clearing.and_then(|clearing| {
+                clearing
+                    .fills
+                    .iter()
+                    .max_by_key(|(_, quantity)| *quantity)
+                    .map(|(buyer, _)| buyer.clone())
+            }),
+            units_sold: clearing.map_or(0, |clearing| clearing.units_sold()),
+            item_hash,
+            supplier,
+        }
+    }
+}
+
+/// Result of an auction for one procurement request
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct ProcurementOutcome {
+    pub request_id: ItemHash,
+    pub buyer: AccountId,
+    pub seller: Option<AccountId>, // None if nobody has made an ask or the auction has been cancelled
+    pub price: Money,              // paid from the budget to the seller
+}
+
+/// How an auction has been finished
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RoundStatus {
+    Completed,
+    Cancelled { reason: String },
+}
+
+/// Completed auction. Lots, procurements and refunds are kept in their own collections so that a cancelled
+/// auction can be archived in several calls
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct RoundRecord {
+    pub id: u64,
+    pub status: RoundStatus,
+    pub started_at: u64,
+    pub ended_at: u64,
+    pub lots: Vector<LotOutcome>,
+    pub procurements: Vector<ProcurementOutcome>,
+    pub refunds: Vector<(AccountId, Money)>, // money that has been returned to bidders
+    pub fees: Money,                         // money that the contract has kept
+}
+
+impl Auction {
+    /// collect results of the current auction for each lot. Has to be called before lots are sold
+    ///
+    /// # Arguments
+    ///
+    /// * `sales` - buyers and price of each sold lot
+    pub(crate) fn collect_lot_outcomes(
+        &mut self,
+        sales: &[(ItemHash, Clearing)],
+    ) -> Vector<LotOutcome> {
+        let mut outcomes = Vector::new(StorageKey::RoundLots { round: self.round });
+
+        for (item_hash, supplier) in self.lots.iter() {
+            let clearing = sales
+                .iter()
+                .find(|(sold_item, _)| *sold_item == item_hash)
+                .map(|(_, clearing)| clearing);
+
+            outcomes.push(&LotOutcome::new(item_hash, supplier, clearing));
+        }
+
+        outcomes
+    }
+
+    /// save the current auction to the archive
+    ///
+    /// # Arguments
+    ///
+    /// * `lots` - results of the auction for each lot
+    /// * `procurements` - results of the auction for each procurement request
+    /// * `refunds` - money that has been returned to bidders
+    /// * `fees` - money that the contract has kept
+    /// * `status` - whether the auction has been completed or cancelled
+    pub(crate) fn archive_round(
+        &mut self,
+        lots: Vector<LotOutcome>,
+        procurements: Vector<ProcurementOutcome>,
+        refunds: Vector<(AccountId, Money)>,
+        fees: Money,
+        status: RoundStatus,
+    ) {
+        self.rounds.push(&RoundRecord {
+            id: self.round,
+            status,
+            started_at: self.round_started_at,
+            ended_at: env::block_timestamp(),
+            lots,
+            procurements,
+            refunds,
+            fees,
+        });
+    }
+}
+
+#[near_bindgen]
+impl Auction {
+    /// return completed auction
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - id of the auction. The first auction has id 1
+    pub fn get_round(&self, id: U64) -> Option<RoundView> {
+        // auctions are archived in the order of their ids
+        let index = id.0.checked_sub(1)?;
+        self.rounds.get(index).map(RoundView::from)
+    }
+
+    /// return completed auctions, oldest first
+    ///
+    /// # Arguments
+    ///
+    /// * `from_index` - index of the first auction. 0 if not set
+    /// * `limit` - max amount of auctions. `DEFAULT_PAGE_LIMIT` if not set
+    pub fn get_rounds(&self, from_index: Option<U64>, limit: Option<U64>) -> Vec<RoundView> {
+        self.rounds
+            .iter()
+            .skip(from_index.map_or(0, |index| index.0) as usize)
+            .take(limit.map_or(DEFAULT_PAGE_LIMIT, |limit| limit.0) as usize)
+            .map(RoundView::from)
+            .collect()
+    }
+}
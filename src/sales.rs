@@ -0,0 +1,197 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::Vector;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::supplier::Supplier;
+use crate::views::{LotView, PurchaseView, SaleView, SupplierView};
+use crate::{Auction, AuctionExt, ItemHash, Money, StorageKey, DEFAULT_PAGE_LIMIT};
+
+/// Item that a supplier has sold in one of the auctions
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct Sale {
+    pub item_hash: ItemHash,
+    pub item: String,
+    pub buyer: AccountId,
+    pub price: Money,  // clearing price that has been sent to the supplier
+    pub quantity: u64, // amount of sold units
+    pub round: u64,
+    pub sold_at: u64,
+}
+
+/// Item that a buyer has won in one of the auctions
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct Purchase {
+    pub item_hash: ItemHash,
+    pub item: String,
+    pub supplier: AccountId,
+    pub price: Money,  // winning bid that has been sent to the supplier
+    pub quantity: u64, // amount of won units
+    pub round: u64,
+    pub purchased_at: u64,
+}
+
+impl Auction {
+    /// save won item to the buyer's purchases
+    ///
+    /// # Arguments
+    ///
+    /// * `buyer` - winner of the item
+    /// * `purchase` - won item
+    pub(crate) fn record_purchase(&mut self, buyer: &AccountId, purchase: Purchase) {
+        let mut purchases = self.winners_items.get(buyer).unwrap_or_else(|| {
+            Vector::new(StorageKey::Purchases {
+                account_hash: env::sha256_array(buyer.as_bytes()),
+            })
+        });
+
+        purchases.push(&purchase);
+
+        self.winners_items.insert(buyer, &purchases);
+    }
+
+    /// save sale to the supplier's history and count its price as paid proceeds
+    ///
+    /// # Arguments
+    ///
+    /// * `supplier` - seller of the item
+    /// * `sale` - sold item
+    pub(crate) fn record_sale(&mut self, supplier: &AccountId, sale: Sale) {
+        let mut sales = self.supplier_sales.get(supplier).unwrap_or_else(|| {
+            Vector::new(StorageKey::Sales {
+                account_hash: env::sha256_array(supplier.as_bytes()),
+            })
+        });
+
+        let paid_proceeds = self.paid_proceeds.get(supplier).unwrap_or_default() + sale.price;
+
+        sales.push(&sale);
+
+        self.supplier_sales.insert(supplier, &sales);
+        self.paid_proceeds.insert(supplier, &paid_proceeds);
+    }
+
+    /// money that the supplier will get for all its lots if the auction is finished now
+    ///
+    /// Bundle bids compete with single bids for other suppliers' lots, so the whole auction is
+    /// allocated only when a bundle includes one of the supplier's lots. Otherwise the biggest
+    /// single bid wins each lot
+    fn pending_proceeds(&self, supplier: &Supplier) -> Money {
+        let is_supplied =
+            |item_hash: &ItemHash| self.lots.get(item_hash).as_ref() == Some(&supplier.id);
+
+        let in_bundles = self
+            .bundle_bids
+            .iter()
+            .any(|bundle| bundle.items.iter().any(is_supplied));
+
+        let single_prices: Money = if in_bundles {
+            self.winning_prices()
+                .into_iter()
+                .filter(|(item_hash, _)| is_supplied(item_hash))
+                .map(|(_, price)| price)
+                .sum()
+        } else {
+            supplier
+                .items
+                .keys()
+                .filter_map(|item_hash| self.items_and_bids.get(&item_hash))
+                .map(|bid| bid.bid)
+                .sum()
+        };
+
+        let unit_totals: Money = supplier
+            .items
+            .keys()
+            .map(|item_hash| self.unit_lot_total(&item_hash))
+            .sum();
+
+        single_prices + unit_totals
+    }
+}
+
+#[near_bindgen]
+impl Auction {
+    /// return supplier's live lots, sold items and proceeds
+    ///
+    /// # Arguments
+    ///
+    /// * `account_id` - supplier
+    /// * `from_index` - index of the first sold item, oldest first. 0 if not set
+    /// * `limit` - max amount of sold items. `DEFAULT_PAGE_LIMIT` if not set
+    /// * `lots_from_index` - index of the first live lot. 0 if not set
+    /// * `lots_limit` - max amount of live lots. `DEFAULT_PAGE_LIMIT` if not set
+    pub fn get_supplier(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+        lots_from_index: Option<U64>,
+        lots_limit: Option<U64>,
+    ) -> SupplierView {
+        let (lots, lots_count, pending_proceeds) = match self.suppliers.get(&account_id) {
+            Some(supplier) => (
+                supplier
+                    .items
+                    .iter()
+                    .skip(lots_from_index.map_or(0, |index| index.0) as usize)
+                    .take(lots_limit.map_or(DEFAULT_PAGE_LIMIT, |limit| limit.0) as usize)
+                    .map(|(item_hash, item)| {
+                        let bid = self.items_and_bids.get(&item_hash);
+                        LotView::new(item_hash, item, &supplier.id, bid)
+                    })
+                    .collect(),
+                supplier.items.len(),
+                self.pending_proceeds(&supplier),
+            ),
+            None => (vec![], 0, 0),
+        };
+
+        let (sales, sales_count) = match self.supplier_sales.get(&account_id) {
+            Some(sales) => (
+                sales
+                    .iter()
+                    .skip(from_index.map_or(0, |index| index.0) as usize)
+                    .take(limit.map_or(DEFAULT_PAGE_LIMIT, |limit| limit.0) as usize)
+                    .map(SaleView::from)
+                    .collect(),
+                sales.len(),
+            ),
+            None => (vec![], 0),
+        };
+
+        SupplierView {
+            paid_proceeds: U128(self.paid_proceeds.get(&account_id).unwrap_or_default()),
+            account_id,
+            lots,
+            lots_count: U64(lots_count),
+            sales,
+            sales_count: U64(sales_count),
+            pending_proceeds: U128(pending_proceeds),
+        }
+    }
+
+    /// return items that the account has won with their prices, suppliers and rounds, oldest first
+    ///
+    /// # Arguments
+    ///
+    /// * `account_id` - buyer
+    /// * `from_index` - index of the first purchase. 0 if not set
+    /// * `limit` - max amount of purchases. `DEFAULT_PAGE_LIMIT` if not set
+    pub fn get_purchases(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<PurchaseView> {
+        match self.winners_items.get(&account_id) {
+            Some(purchases) => purchases
+                .iter()
+                .skip(from_index.map_or(0, |index| index.0) as usize)
+                .take(limit.map_or(DEFAULT_PAGE_LIMIT, |limit| limit.0) as usize)
+                .map(PurchaseView::from)
+                .collect(),
+            None => vec![],
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use ed25519_dalek::{PublicKey, Signature};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base64VecU8, U128, U64};
+use near_sdk::{env, near_bindgen, AccountId, Promise};
+
+use crate::errors::AuctionError;
+use crate::solvency::pay_for_storage;
+use crate::views::BidKeyView;
+use crate::{Auction, AuctionExt, ItemHash, Money};
+
+/// Public ed25519 key that signs the account's bids off-chain
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct BidKey {
+    pub public_key: Vec<u8>,
+    pub last_nonce: u64, // nonce of the last accepted signed bid. Only bigger nonces are accepted
+}
+
+/// Bid that a bidder signs off-chain. The signed message is the borsh serialization of it
+#[derive(BorshSerialize, Debug, Clone)]
+pub struct SignedBid {
+    pub contract_id: AccountId, // signed bids of one contract can not be replayed on another one
+    pub bidder: AccountId,
+    pub item_hash: ItemHash,
+    pub amount: Money,
+    pub nonce: u64,
+    pub expires_at: u64, // nanoseconds
+}
+
+impl SignedBid {
+    /// check that the bid has been signed by the key
+    ///
+    /// # Arguments
+    ///
+    /// * `public_key` - 32 bytes of an ed25519 public key
+    /// * `signature` - 64 bytes of an ed25519 signature
+    pub fn is_signed_by(&self, public_key: &[u8], signature: &[u8]) -> bool {
+        let public_key = match PublicKey::from_bytes(public_key) {
+            Ok(public_key) => public_key,
+            Err(_) => return false,
+        };
+
+        let signature = match Signature::try_from(signature) {
+            Ok(signature) => signature,
+            Err(_) => return false,
+        };
+
+        let message = self.try_to_vec().unwrap();
+
+        public_key.verify_strict(&message, &signature).is_ok()
+    }
+}
+
+#[near_bindgen]
+impl Auction {
+    /// register a key that signs caller's bids off-chain, so relayers can submit them with
+    /// `make_bid_signed`. The previous key of the caller is replaced. The first key is paid with
+    /// a storage deposit, the rest of the deposit is returned
+    ///
+    /// # Arguments
+    ///
+    /// * `public_key` - 32 bytes of an ed25519 public key
+    ///
+    /// # Panics
+    ///
+    /// * contract is paused
+    /// * key is not a valid ed25519 public key
+    /// * deposit does not cover storage of the first key
+    #[payable]
+    pub fn register_bid_key(&mut self, public_key: Base64VecU8) {
+        self.assert_not_paused();
+        if PublicKey::from_bytes(&public_key.0).is_err() {
+            AuctionError::InvalidPublicKey.panic()
+        }
+
+        let account_id = env::predecessor_account_id();
+        // nonces keep growing so signatures of the previous key can not be replayed
+        let last_nonce = self
+            .bid_keys
+            .get(&account_id)
+            .map_or(0, |key| key.last_nonce);
+
+        let storage_usage = env::storage_usage();
+        self.bid_keys.insert(
+            &account_id,
+            &BidKey {
+                public_key: public_key.0,
+                last_nonce,
+            },
+        );
+
+        let refund = pay_for_storage(storage_usage);
+        if refund > 0 {
+            Promise::new(account_id).transfer(refund);
+        }
+    }
+
+    /// return the key that signs bids of the account and the nonce of its last signed bid
+    pub fn get_bid_key(&self, account_id: AccountId) -> Option<BidKeyView> {
+        self.bid_keys.get(&account_id).map(BidKeyView::from)
+    }
+
+    /// make bid on behalf of the bidder who has signed it off-chain. The caller pays gas, the bid
+    /// is locked in the bidder's free balance (see `deposit`)
+    ///
+    /// # Arguments
+    ///
+    /// * `bidder` - account that has signed the bid
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `amount` - bid
+    /// * `nonce` - must be bigger than the nonce of the bidder's last signed bid
+    /// * `expires_at` - the bid is not accepted after this moment (nanoseconds)
+    /// * `signature` - ed25519 signature of the borsh serialized `SignedBid`
+    ///
+    /// # Panics
+    ///
+    ///  * bidder has not registered a bid key
+    ///  * bid has expired or its nonce has already been used
+    ///  * signature is invalid
+    ///  * bid is invalid (see `make_bid`)
+    pub fn make_bid_signed(
+        &mut self,
+        bidder: AccountId,
+        item_hash: ItemHash,
+        amount: U128,
+        nonce: U64,
+        expires_at: U64,
+        signature: Base64VecU8,
+    ) {
+        self.assert_can_bid(&bidder);
+
+        let mut key = self.bid_keys.get(&bidder).unwrap_or_else(|| {
+            AuctionError::BidKeyNotFound {
+                account_id: bidder.clone(),
+            }
+            .panic()
+        });
+
+        if env::block_timestamp() > expires_at.0 {
+            AuctionError::SignedBidExpired.panic()
+        }
+        if nonce.0 <= key.last_nonce {
+            AuctionError::InvalidNonce {
+                last_nonce: U64(key.last_nonce),
+                nonce,
+            }
+            .panic()
+        }
+
+        let signed_bid = SignedBid {
+            contract_id: env::current_account_id(),
+            bidder: bidder.clone(),
+            item_hash: item_hash.clone(),
+            amount: amount.0,
+            nonce: nonce.0,
+            expires_at: expires_at.0,
+        };
+
+        if !signed_bid.is_signed_by(&key.public_key, &signature.0) {
+            AuctionError::InvalidSignature.panic()
+        }
+
+        key.last_nonce = nonce.0;
+        self.bid_keys.insert(&bidder, &key);
+
+        self.place_bid(&bidder, &item_hash, amount.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn get_bid_keypair() -> ed25519_dalek::Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[7; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+
+        ed25519_dalek::Keypair { secret, public }
+    }
+
+    fn sign_bid(item_hash: &ItemHash, amount: Money, nonce: u64) -> Base64VecU8 {
+        use ed25519_dalek::Signer;
+        use near_sdk::borsh::BorshSerialize;
+
+        let signed_bid = crate::signed_bids::SignedBid {
+            contract_id: account("alice.near"),
+            bidder: account("carol.near"),
+            item_hash: item_hash.clone(),
+            amount,
+            nonce,
+            expires_at: 100,
+        };
+
+        let signature = get_bid_keypair().sign(&signed_bid.try_to_vec().unwrap());
+        Base64VecU8(signature.to_bytes().to_vec())
+    }
+
+    fn setup_signed_bids() -> (TestAuction, ItemHash) {
+        let (mut exchange, item_hash) = setup_auction_with_item(5);
+
+        deposit_exactly(&mut exchange, "carol.near", 50);
+        call_contract("carol.near", NEAR);
+        exchange.register_bid_key(Base64VecU8(get_bid_keypair().public.to_bytes().to_vec()));
+
+        // the relayer pays gas for the bidder
+        call_contract("relayer.near", 0);
+
+        (exchange, item_hash)
+    }
+
+    fn make_signed_bid(exchange: &mut Auction, item_hash: &ItemHash, amount: Money, nonce: u64) {
+        exchange.make_bid_signed(
+            account("carol.near"),
+            item_hash.clone(),
+            U128(amount),
+            U64(nonce),
+            U64(100),
+            sign_bid(item_hash, amount, nonce),
+        );
+    }
+
+    #[test]
+    fn test_make_bid_signed() {
+        let (mut exchange, item_hash) = setup_signed_bids();
+
+        make_signed_bid(&mut exchange, &item_hash, 10, 1);
+
+        let lot = exchange.get_lot(item_hash).unwrap();
+        assert_eq!(lot.winner, Some(account("carol.near")));
+
+        let carol = account("carol.near");
+        assert_eq!(exchange.get_balance(carol.clone()).locked, U128(10));
+        assert_eq!(exchange.get_bid_key(carol).unwrap().last_nonce, U64(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Nonce must be bigger than 1. Actual: 1")]
+    fn test_signed_bid_can_not_be_replayed() {
+        let (mut exchange, item_hash) = setup_signed_bids();
+
+        make_signed_bid(&mut exchange, &item_hash, 10, 1);
+        make_signed_bid(&mut exchange, &item_hash, 10, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid signature")]
+    fn test_signed_bid_with_changed_amount() {
+        let (mut exchange, item_hash) = setup_signed_bids();
+
+        exchange.make_bid_signed(
+            account("carol.near"),
+            item_hash.clone(),
+            U128(20),
+            U64(1),
+            U64(100),
+            sign_bid(&item_hash, 10, 1),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Signed bid has expired")]
+    fn test_expired_signed_bid() {
+        let (mut exchange, item_hash) = setup_signed_bids();
+
+        call_at("bob.near", 0, 101);
+        make_signed_bid(&mut exchange, &item_hash, 10, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account carol.near has not registered a bid key")]
+    fn test_signed_bid_without_key() {
+        let (mut exchange, item_hash) = setup_auction_with_item(5);
+        make_signed_bid(&mut exchange, &item_hash, 10, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid_public_key: Invalid public key")]
+    fn test_register_invalid_bid_key() {
+        let mut exchange = TestAuction::new();
+        exchange.register_bid_key(Base64VecU8(vec![1, 2, 3]));
+    }
+
+    #[test]
+    #[should_panic(expected = "storage_not_covered: Attached deposit does not cover storage")]
+    fn test_register_bid_key_without_storage_deposit() {
+        let mut exchange = TestAuction::new();
+        exchange.register_bid_key(Base64VecU8(get_bid_keypair().public.to_bytes().to_vec()));
+    }
+
+    #[test]
+    #[should_panic(expected = "contract_paused: Contract is paused")]
+    fn test_register_bid_key_when_paused() {
+        let mut exchange = TestAuction::new();
+        exchange.pause();
+
+        call_contract("carol.near", NEAR);
+        exchange.register_bid_key(Base64VecU8(get_bid_keypair().public.to_bytes().to_vec()));
+    }
+
+    #[test]
+    fn test_replace_bid_key_for_free() {
+        let mut exchange = TestAuction::new();
+        let public_key = Base64VecU8(get_bid_keypair().public.to_bytes().to_vec());
+        call(&mut exchange, "carol.near", NEAR, |exchange| {
+            exchange.register_bid_key(public_key.clone())
+        });
+
+        // the new key takes the storage of the old one
+        call(&mut exchange, "carol.near", 0, |exchange| {
+            exchange.register_bid_key(public_key)
+        });
+    }
+}
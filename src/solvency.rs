@@ -0,0 +1,80 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, StorageUsage};
+
+use crate::errors::AuctionError;
+use crate::views::LiabilitiesView;
+use crate::{Auction, AuctionExt, Money};
+
+#[near_bindgen]
+impl Auction {
+    /// return money that the contract owes to bidders and suppliers and the balance that covers
+    /// it. Proceeds of sold lots and forfeited bonds are transferred as soon as the auction is
+    /// produced or the lot is delisted, so the contract never keeps them
+    pub fn get_liabilities(&self) -> LiabilitiesView {
+        let locked = self.escrow_total.locked;
+        let withdrawable = self.escrow_total.free;
+        let bonds = self.bonds_total;
+
+        let total = locked + withdrawable + bonds;
+        let balance = available_balance();
+
+        LiabilitiesView {
+            locked: U128(locked),
+            withdrawable: U128(withdrawable),
+            bonds: U128(bonds),
+            total: U128(total),
+            balance: U128(balance),
+            solvent: total <= balance,
+        }
+    }
+}
+
+impl Auction {
+    /// debug check that unit tests run after state changes to catch money that has been lost
+    /// or counted twice
+    ///
+    /// # Panics
+    ///
+    /// * contract balance does not cover its liabilities
+    #[cfg(test)]
+    pub(crate) fn assert_solvent(&self) {
+        let liabilities = self.get_liabilities();
+
+        if !liabilities.solvent {
+            AuctionError::Insolvent {
+                liabilities: liabilities.total,
+                balance: liabilities.balance,
+            }
+            .panic()
+        }
+    }
+}
+
+/// contract balance that is not reserved for storage staking
+pub fn available_balance() -> Money {
+    let storage_cost = env::storage_usage() as Money * env::storage_byte_cost();
+
+    env::account_balance().saturating_sub(storage_cost)
+}
+
+/// take the cost of storage that the call has used since `storage_usage` from the attached
+/// deposit, so the contract does not pay for entries of its users. Return the rest of the deposit
+///
+/// # Panics
+///
+/// * attached deposit does not cover the storage
+pub fn pay_for_storage(storage_usage: StorageUsage) -> Money {
+    let used = env::storage_usage().saturating_sub(storage_usage);
+    let required = used as Money * env::storage_byte_cost();
+    let attached = env::attached_deposit();
+
+    if attached < required {
+        AuctionError::StorageNotCovered {
+            required: U128(required),
+            attached: U128(attached),
+        }
+        .panic()
+    }
+
+    attached - required
+}
@@ -1,9 +1,12 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupSet, UnorderedMap};
 use near_sdk::Balance;
-use near_sdk::{collections::UnorderedMap, env, AccountId};
+use near_sdk::{env, AccountId};
 use sha2::{Digest, Sha256};
 
-use crate::helper::Helper;
+use crate::errors::AuctionError;
+use crate::metadata::ItemMetadata;
+use crate::StorageKey;
 
 pub type ItemHash = String;
 
@@ -13,17 +16,31 @@ pub const DEFAULT_MIN_BID: u128 = 1;
 pub struct Item {
     pub min_bid: u128,
     pub itself: String,
+    pub content_hash: String, // SHA256 of the item itself. Lots are identified by their own id, not by this hash
+    pub metadata: Option<ItemMetadata>,
+    pub has_bids: bool, // seller can not change or withdraw the item after the first bid
+    pub created_at: u64, // block timestamp in nanoseconds
+    pub bond: Balance,  // deposit that the supplier has paid for listing the item
+    pub quantity: u64,  // amount of identical units in the lot. 1 for single items
 }
 
 impl Item {
-    pub fn new(item: &String, min_price: &u128) -> (Self, ItemHash) {
-        (
-            Self {
-                min_bid: *min_price,
-                itself: item.to_string(),
-            },
-            get_hash(item),
-        )
+    pub fn new(
+        item: &str,
+        min_price: &u128,
+        metadata: Option<ItemMetadata>,
+        bond: Balance,
+    ) -> Self {
+        Self {
+            min_bid: *min_price,
+            itself: item.to_string(),
+            content_hash: get_hash(item),
+            metadata,
+            has_bids: false,
+            created_at: env::block_timestamp(),
+            bond,
+            quantity: 1,
+        }
     }
 }
 
@@ -32,73 +49,262 @@ impl Item {
 pub struct Supplier {
     pub id: AccountId,
     pub items: UnorderedMap<ItemHash, Item>,
+    pub contents: LookupSet<String>, // content hashes of the items, so duplicates are found without a scan
 }
 
 impl Supplier {
-    pub fn new(helper: &mut Helper) -> Self {
+    /// supplier of the auction round. Its collections are prefixed with the account and the round
+    pub fn new(round: u64) -> Self {
+        let id = env::predecessor_account_id();
+        let account_hash = env::sha256_array(id.as_bytes());
+
         Self {
-            id: env::predecessor_account_id(),
-            items: UnorderedMap::new(helper.generate_collection_id()),
+            items: UnorderedMap::new(StorageKey::SupplierItems {
+                account_hash,
+                round,
+            }),
+            contents: LookupSet::new(StorageKey::SupplierContents {
+                account_hash,
+                round,
+            }),
+            id,
         }
     }
 
-    /// add item to an supplier's internal list
+    /// add item to an supplier's internal list and return id of the created lot
     ///
     /// # Arguments
     ///
     /// * `item` - represent of an item in a string format
     /// * `min_price` - minimal price that buyers can. Will be changed to 1 yocto token if 0
-    pub fn add_item_to_auction(&mut self, item: &String, min_price: &u128) {
-        let correct_min_price = if DEFAULT_MIN_BID < *min_price {
-            *min_price
-        } else {
-            DEFAULT_MIN_BID
-        };
-
-        let (item, item_hash) = Item::new(item, &correct_min_price);
+    /// * `metadata` - optional description of the item
+    /// * `round` - id of the auction round the item is added to
+    /// * `nonce` - monotonic number that makes the lot id unique
+    /// * `bond` - deposit that the supplier has paid for listing the item
+    ///
+    /// # Panics
+    ///
+    /// * supplier has already added the same item
+    /// * metadata exceeds size limits (see `ItemMetadata::assert_valid`)
+    pub fn add_item_to_auction(
+        &mut self,
+        item: &str,
+        min_price: &u128,
+        metadata: Option<ItemMetadata>,
+        round: u64,
+        nonce: u64,
+        bond: Balance,
+    ) -> ItemHash {
+        let correct_min_price = correct_min_price(min_price);
+
+        if let Some(metadata) = &metadata {
+            metadata.assert_valid();
+        }
+
+        let item = Item::new(item, &correct_min_price, metadata, bond);
+
+        if self.contains_content(&item.content_hash) {
+            AuctionError::ItemAlreadyAdded {
+                item: item.itself.clone(),
+            }
+            .panic()
+        }
+
+        let item_hash = get_lot_id(&self.id, round, nonce);
+        self.contents.insert(&item.content_hash);
         self.items.insert(&item_hash, &item);
+
+        item_hash
+    }
+
+    /// change amount of units in the lot
+    ///
+    /// # Arguments
+    ///
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `quantity` - amount of identical units
+    ///
+    /// # Panics
+    ///
+    /// * supplier has not added item with such hash
+    /// * somebody has already made a bid for the item
+    /// * quantity is 0
+    pub fn set_quantity(&mut self, item_hash: &ItemHash, quantity: u64) {
+        if quantity == 0 {
+            AuctionError::InvalidQuantity.panic()
+        }
+
+        let mut item = self.get_unbid_item(item_hash);
+        item.quantity = quantity;
+
+        self.items.insert(item_hash, &item);
     }
 
     /// remove item from the supplier's internal list
     ///
     /// # Arguments
-    /// * `item_hash` - hash calculated from an item through the SHA256 algorithm
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
     pub fn sell_item(&mut self, item_hash: &ItemHash) -> Option<Item> {
-        self.items.remove(&item_hash)
+        self.take_item(item_hash)
+    }
+
+    /// withdraw item from an auction by the seller
+    ///
+    /// # Arguments
+    ///
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    ///
+    /// # Panics
+    ///
+    /// * supplier has not added item with such hash
+    /// * somebody has already made a bid for the item
+    pub fn remove_item(&mut self, item_hash: &ItemHash) -> Item {
+        let item = self.get_unbid_item(item_hash);
+        self.take_item(item_hash);
+
+        item
+    }
+
+    /// remove item by a moderator even if it has bids
+    ///
+    /// # Arguments
+    ///
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    ///
+    /// # Panics
+    ///
+    /// * supplier has not added item with such hash
+    pub fn delist_item(&mut self, item_hash: &ItemHash) -> Item {
+        self.take_item(item_hash)
+            .unwrap_or_else(|| item_not_found(item_hash))
+    }
+
+    /// change min bid and/or metadata of the item
+    ///
+    /// # Arguments
+    ///
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `min_price` - new minimal price. Will be changed to 1 yocto token if 0
+    /// * `metadata` - new description of the item
+    ///
+    /// # Panics
+    ///
+    /// * supplier has not added item with such hash
+    /// * somebody has already made a bid for the item
+    /// * metadata exceeds size limits (see `ItemMetadata::assert_valid`)
+    pub fn update_item(
+        &mut self,
+        item_hash: &ItemHash,
+        min_price: Option<u128>,
+        metadata: Option<ItemMetadata>,
+    ) {
+        let mut item = self.get_unbid_item(item_hash);
+
+        if let Some(min_price) = min_price {
+            item.min_bid = correct_min_price(&min_price);
+        }
+
+        if let Some(metadata) = metadata {
+            metadata.assert_valid();
+            item.metadata = Some(metadata);
+        }
+
+        self.items.insert(item_hash, &item);
+    }
+
+    /// mark the item as having bids so it can not be changed or withdrawn anymore
+    ///
+    /// # Arguments
+    ///
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    pub fn register_bid(&mut self, item_hash: &ItemHash) {
+        if let Some(mut item) = self.items.get(item_hash) {
+            if !item.has_bids {
+                item.has_bids = true;
+                self.items.insert(item_hash, &item);
+            }
+        }
+    }
+
+    /// remove up to `limit` items with their content hashes. Collections of the supplier are not
+    /// removed with the supplier, so they have to be emptied first
+    ///
+    /// return amount of removed items
+    pub fn clear_items(&mut self, limit: usize) -> usize {
+        let item_hashes: Vec<ItemHash> = self.items.keys().take(limit).collect();
+
+        for item_hash in item_hashes.iter() {
+            self.take_item(item_hash);
+        }
+
+        item_hashes.len()
+    }
+
+    /// remove item and its content hash, so the same content can be added again
+    fn take_item(&mut self, item_hash: &ItemHash) -> Option<Item> {
+        let item = self.items.remove(item_hash)?;
+        self.contents.remove(&item.content_hash);
+
+        Some(item)
+    }
+
+    fn get_unbid_item(&self, item_hash: &ItemHash) -> Item {
+        let item = self
+            .items
+            .get(item_hash)
+            .unwrap_or_else(|| item_not_found(item_hash));
+
+        if item.has_bids {
+            AuctionError::ItemHasBids {
+                item_hash: item_hash.clone(),
+            }
+            .panic()
+        }
+
+        item
     }
 
     /// check if selled has added item with such hash to an auction
     ///
     /// # Arguments
     ///
-    /// * `item_hash` - hash calculated from an item through the SHA256 algorithm
-    pub fn contains_item(&self, item_hash: &String) -> bool {
-        self.items.get(&item_hash).is_some()
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    pub fn contains_item(&self, item_hash: &str) -> bool {
+        self.items.get(&item_hash.to_string()).is_some()
+    }
+
+    /// check if seller has already added an item with the same content
+    ///
+    /// # Arguments
+    ///
+    /// * `content_hash` - hash calculated from an item itself through the SHA256 algorithm
+    pub fn contains_content(&self, content_hash: &str) -> bool {
+        self.contents.contains(&content_hash.to_string())
     }
 
     /// return item that has been added to an auction
     ///
     /// # Arguments
     ///
-    /// * `item_hash` - hash calculated from an item through the SHA256 algorithm
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
     ///
     ///  # Panics
     ///
     ///  * Supplier has not added item with such hash
     pub fn get_item(&self, item_hash: &ItemHash) -> String {
-        match self.items.get(&item_hash) {
+        match self.items.get(item_hash) {
             Some(item) => item.itself,
-            None => panic!("supplier does not contain item with hash {}", item_hash),
+            None => item_not_found(item_hash),
         }
     }
 
     /// check if deposit is bigger than item's min bid
     ///
     ///  # Arguments
-    /// * `item_hash` - hash calculated from an item through the SHA256 algorithm
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
     /// * `deposit` - attached deposit in yocto tokens
-    pub fn bid_can_be_done(&mut self, item_hash: &String, deposit: &Balance) -> (bool, Balance) {
-        match self.items.get(item_hash) {
+    pub fn bid_can_be_done(&mut self, item_hash: &str, deposit: &Balance) -> (bool, Balance) {
+        match self.items.get(&item_hash.to_string()) {
             Some(item) => {
                 if item.min_bid > *deposit {
                     return (false, item.min_bid);
@@ -111,10 +317,31 @@ impl Supplier {
     }
 }
 
-fn get_hash(item: &String) -> String {
+fn correct_min_price(min_price: &u128) -> u128 {
+    if DEFAULT_MIN_BID < *min_price {
+        *min_price
+    } else {
+        DEFAULT_MIN_BID
+    }
+}
+
+fn item_not_found(item_hash: &ItemHash) -> ! {
+    AuctionError::ItemNotFound {
+        item_hash: item_hash.clone(),
+    }
+    .panic()
+}
+
+fn get_hash(item: &str) -> String {
     format!("{:X}", Sha256::digest(item.as_bytes()))
 }
 
+/// calculate lot id from the supplier account, auction round and a monotonic nonce
+/// so the same item listed by different suppliers or in different rounds gets different ids
+pub fn get_lot_id(supplier: &AccountId, round: u64, nonce: u64) -> ItemHash {
+    get_hash(&format!("{}:{}:{}", supplier, round, nonce))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -133,33 +360,140 @@ mod tests {
 
     #[test]
     fn test_add_item_to_auction() {
-        let mut supplier = Supplier::new(&mut Helper::new());
-        let (item, item_hash) = Item::new(&"test_item".to_string(), &12u128);
-        supplier.add_item_to_auction(&item.itself, &item.min_bid);
+        let mut supplier = Supplier::new(1);
+        let item = Item::new("test_item", &12u128, None, 0);
+        let item_hash = supplier.add_item_to_auction(&item.itself, &item.min_bid, None, 1, 0, 0);
 
-        assert_eq!(
-            true,
+        assert!(
             supplier.contains_item(&item_hash),
             "item has not been added to a seller"
-        )
+        );
+
+        assert!(
+            supplier.contains_content(&item.content_hash),
+            "item content has not been added to a seller"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Item test_item has already been added to the auction")]
+    fn test_add_duplicate_item() {
+        let mut supplier = Supplier::new(1);
+        supplier.add_item_to_auction("test_item", &12u128, None, 1, 0, 0);
+        supplier.add_item_to_auction("test_item", &15u128, None, 1, 1, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid_quantity: Quantity must be positive")]
+    fn test_set_zero_quantity() {
+        let mut supplier = Supplier::new(1);
+        let item_hash = supplier.add_item_to_auction("test_item", &12u128, None, 1, 0, 0);
+        supplier.set_quantity(&item_hash, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "item_not_found: Item with hash hash does not exist")]
+    fn test_remove_unknown_item() {
+        let mut supplier = Supplier::new(1);
+        supplier.remove_item(&"hash".to_string());
+    }
+
+    #[test]
+    fn test_lot_id_does_not_depend_on_item() {
+        let bob = AccountId::new_unchecked("bob.near".to_string());
+        let alice = AccountId::new_unchecked("alice.near".to_string());
+
+        assert_ne!(get_lot_id(&bob, 1, 0), get_lot_id(&alice, 1, 0));
+        assert_ne!(get_lot_id(&bob, 1, 0), get_lot_id(&bob, 2, 0));
+        assert_ne!(get_lot_id(&bob, 1, 0), get_lot_id(&bob, 1, 1));
     }
 
     #[test]
     fn test_sell_item() {
         let min_bet = 12u128;
 
-        let (item, item_hash) = Item::new(&"test_item".to_string(), &12u128);
+        let item = Item::new("test_item", &12u128, None, 0);
 
-        let mut supplier = Supplier::new(&mut Helper::new());
-        supplier.add_item_to_auction(&item.itself, &item.min_bid);
+        let mut supplier = Supplier::new(1);
+        let item_hash = supplier.add_item_to_auction(&item.itself, &item.min_bid, None, 1, 0, 0);
 
         match supplier.sell_item(&item_hash) {
-            Some(item) => {
-                assert_eq!(item.min_bid, min_bet, "wrong item has been selled");
-                assert_eq!(item.itself, item.itself, "wrong item has been selled");
+            Some(sold_item) => {
+                assert_eq!(sold_item.min_bid, min_bet, "wrong item has been selled");
+                assert_eq!(sold_item.itself, item.itself, "wrong item has been selled");
             }
 
             None => panic!("suppliyer still contains item after a sell"),
         }
     }
+
+    #[test]
+    fn test_remove_item() {
+        let mut supplier = Supplier::new(1);
+        let item_hash = supplier.add_item_to_auction("test_item", &12u128, None, 1, 0, 0);
+
+        let removed_item = supplier.remove_item(&item_hash);
+
+        assert_eq!(removed_item.itself, "test_item");
+        assert!(!supplier.contains_item(&item_hash));
+        assert!(!supplier.contains_content(&removed_item.content_hash));
+    }
+
+    #[test]
+    fn test_add_removed_item_again() {
+        let mut supplier = Supplier::new(1);
+        let item_hash = supplier.add_item_to_auction("test_item", &12u128, None, 1, 0, 0);
+        supplier.remove_item(&item_hash);
+
+        let item_hash = supplier.add_item_to_auction("test_item", &12u128, None, 1, 1, 0);
+        assert!(supplier.contains_item(&item_hash));
+    }
+
+    #[test]
+    fn test_clear_items_in_batches() {
+        let mut supplier = Supplier::new(1);
+        for (nonce, item) in ["chair", "table", "lamp"].iter().enumerate() {
+            supplier.add_item_to_auction(item, &12u128, None, 1, nonce as u64, 0);
+        }
+
+        assert_eq!(supplier.clear_items(2), 2);
+        assert_eq!(supplier.items.len(), 1);
+        assert_eq!(supplier.clear_items(2), 1);
+        assert!(supplier.items.is_empty());
+        assert!(!supplier.contains_content(&get_hash("chair")));
+    }
+
+    #[test]
+    #[should_panic(expected = "already has bids")]
+    fn test_remove_item_with_bids() {
+        let mut supplier = Supplier::new(1);
+        let item_hash = supplier.add_item_to_auction("test_item", &12u128, None, 1, 0, 0);
+
+        supplier.register_bid(&item_hash);
+        supplier.remove_item(&item_hash);
+    }
+
+    #[test]
+    fn test_update_item() {
+        let mut supplier = Supplier::new(1);
+        let item_hash = supplier.add_item_to_auction("test_item", &12u128, None, 1, 0, 0);
+
+        supplier.update_item(&item_hash, Some(0), None);
+
+        assert_eq!(
+            supplier.items.get(&item_hash).unwrap().min_bid,
+            DEFAULT_MIN_BID,
+            "min bid has not been updated"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "already has bids")]
+    fn test_update_item_with_bids() {
+        let mut supplier = Supplier::new(1);
+        let item_hash = supplier.add_item_to_auction("test_item", &12u128, None, 1, 0, 0);
+
+        supplier.register_bid(&item_hash);
+        supplier.update_item(&item_hash, Some(20), None);
+    }
 }
@@ -0,0 +1,149 @@
+use std::ops::{Deref, DerefMut};
+use std::thread;
+
+use near_sdk::json_types::U128;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{env, testing_env, AccountId};
+
+use crate::{Auction, ItemHash, Money};
+
+/// contract of a unit test. The contract must be solvent when the test is over, unless the test
+/// has already failed
+pub(crate) struct TestAuction(Auction);
+
+impl TestAuction {
+    pub(crate) fn new() -> Self {
+        Self(Auction::new())
+    }
+}
+
+impl Deref for TestAuction {
+    type Target = Auction;
+
+    fn deref(&self) -> &Auction {
+        &self.0
+    }
+}
+
+impl DerefMut for TestAuction {
+    fn deref_mut(&mut self) -> &mut Auction {
+        &mut self.0
+    }
+}
+
+impl Drop for TestAuction {
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            self.0.assert_solvent();
+        }
+    }
+}
+
+pub(crate) fn account(account_id: &str) -> AccountId {
+    AccountId::new_unchecked(account_id.to_string())
+}
+
+/// owner of the contract in tests
+pub(crate) fn get_acc_id() -> AccountId {
+    account("bob.near")
+}
+
+pub(crate) const NEAR: Money = 10u128.pow(24);
+
+/// covers storage of a single entry, the rest of it is returned
+pub(crate) const STORAGE_DEPOSIT: Money = NEAR / 100;
+
+const BIDDERS: [&str; 3] = ["alice.near", "carol.near", "dave.near"];
+
+/// call the contract the way the chain does: the attached deposit is added to the contract
+/// balance, transfers, storage usage and time of the previous call are kept
+pub(crate) fn call_contract(account_id: &str, deposit: Money) {
+    call_at(account_id, deposit, env::block_timestamp());
+}
+
+/// call the contract like `call_contract` does at the given block time
+pub(crate) fn call_at(account_id: &str, deposit: Money, timestamp: u64) {
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(account(account_id))
+        .attached_deposit(deposit)
+        .account_balance(env::account_balance())
+        .storage_usage(env::storage_usage())
+        .block_timestamp(timestamp)
+        .build());
+}
+
+/// put exactly `amount` to the free balance of the account, paying storage of the balance
+/// from a bigger deposit and withdrawing the rest
+pub(crate) fn deposit_exactly(exchange: &mut Auction, account_id: &str, amount: Money) {
+    call_contract(account_id, amount + NEAR);
+    exchange.deposit();
+
+    call_contract(account_id, 0);
+    let free = exchange.get_balance(account(account_id)).free;
+    exchange.withdraw(Some(U128(free.0 - amount)));
+}
+
+/// call the contract method like `call_contract` does and check that the contract is still
+/// solvent after it
+pub(crate) fn call<R>(
+    exchange: &mut Auction,
+    account_id: &str,
+    deposit: Money,
+    method: impl FnOnce(&mut Auction) -> R,
+) -> R {
+    call_contract(account_id, deposit);
+    let result = method(exchange);
+    exchange.assert_solvent();
+
+    result
+}
+
+/// contract that has money only for its storage and a small reserve for storage growth
+pub(crate) fn setup_funded_auction() -> TestAuction {
+    call_contract("bob.near", 0);
+    let storage_cost = env::storage_usage() as Money * env::storage_byte_cost();
+    testing_env!(VMContextBuilder::new()
+        .account_balance(storage_cost + NEAR / 10)
+        .build());
+
+    let mut exchange = TestAuction::new();
+    call(&mut exchange, "bob.near", 0, |exchange| {
+        exchange.set_listing_bond(U128(NEAR))
+    });
+    call(&mut exchange, "bob.near", 0, Auction::start_new_auction);
+    open_balances(&mut exchange);
+
+    exchange
+}
+
+pub(crate) fn add_funded_item(exchange: &mut Auction, item: &str) -> ItemHash {
+    call(exchange, "bob.near", NEAR, |exchange| {
+        exchange.add_item_to_auction(&item.to_string(), U128(NEAR), None)
+    })
+}
+
+/// open empty balances of the test bidders, so their first bids do not pay for storage. The
+/// owner calls the contract next
+pub(crate) fn open_balances(exchange: &mut Auction) {
+    for bidder in BIDDERS {
+        deposit_exactly(exchange, bidder, 0);
+    }
+    call_contract("bob.near", 0);
+}
+
+/// auction of the owner that is started and has no lots
+pub(crate) fn setup_auction() -> TestAuction {
+    let mut exchange = TestAuction::new();
+    exchange.start_new_auction();
+    open_balances(&mut exchange);
+
+    exchange
+}
+
+/// started auction with a single lot of the owner
+pub(crate) fn setup_auction_with_item(min_bid: Money) -> (TestAuction, ItemHash) {
+    let mut exchange = setup_auction();
+    let item_hash = exchange.add_item_to_auction(&"test_item".to_string(), U128(min_bid), None);
+
+    (exchange, item_hash)
+}
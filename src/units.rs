@@ -0,0 +1,604 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::Vector;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::errors::AuctionError;
+use crate::history::{BidKind, BidStatus};
+use crate::metadata::ItemMetadata;
+use crate::views::UnitBidView;
+use crate::{Auction, AuctionExt, Bid, Item, ItemHash, Money, StorageKey, DEFAULT_PAGE_LIMIT};
+
+/// Bid for several units of a multi-unit lot
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct UnitBid {
+    pub account_id: AccountId,
+    pub quantity: u64,
+    pub unit_price: Money, // max price that the bidder pays for one unit
+    pub created_at: u64,
+    pub record: u64, // index of the bid in the history of the lot
+}
+
+impl UnitBid {
+    /// money locked in the bidder's balance until the auction is finished
+    pub fn amount(&self) -> Money {
+        self.unit_price * self.quantity as Money
+    }
+}
+
+/// Units of a lot that have been sold and the price of one unit that every buyer pays
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clearing {
+    pub fills: Vec<(AccountId, u64)>, // buyer and amount of units, one entry per buyer
+    pub unit_price: Money,
+}
+
+impl Clearing {
+    /// add units to the buyer's fill
+    pub fn add_fill(&mut self, buyer: AccountId, quantity: u64) {
+        match self
+            .fills
+            .iter_mut()
+            .find(|(account_id, _)| *account_id == buyer)
+        {
+            Some((_, filled)) => *filled += quantity,
+            None => self.fills.push((buyer, quantity)),
+        }
+    }
+
+    /// money that buyers pay for all sold units
+    pub fn total(&self) -> Money {
+        self.fills
+            .iter()
+            .map(|(_, quantity)| self.unit_price * *quantity as Money)
+            .sum()
+    }
+
+    /// amount of sold units
+    pub fn units_sold(&self) -> u64 {
+        self.fills.iter().map(|(_, quantity)| quantity).sum()
+    }
+}
+
+impl From<Bid> for Clearing {
+    fn from(bid: Bid) -> Self {
+        Self {
+            fills: vec![(bid.account_id, 1)],
+            unit_price: bid.bid,
+        }
+    }
+}
+
+/// sell units to the biggest bids with a uniform price. Bids are filled from the biggest unit
+/// price down, earlier bids first when prices are equal, until units run out. The last filled
+/// bid can be filled partially. Every buyer pays the unit price of the last filled bid, that is
+/// the lowest accepted price
+///
+/// # Arguments
+///
+/// * `quantity` - amount of units in the lot
+/// * `bids` - bids for the lot in the order they have been made
+///
+/// return amount of filled units for each bid and the clearing unit price. The price is 0 if
+/// nothing has been filled
+pub fn clear_uniform_price(quantity: u64, bids: &[UnitBid]) -> (Vec<u64>, Money) {
+    let mut order: Vec<usize> = (0..bids.len()).collect();
+    // stable sort keeps earlier bids first when prices are equal
+    order.sort_by(|a, b| bids[*b].unit_price.cmp(&bids[*a].unit_price));
+
+    let mut fills = vec![0; bids.len()];
+    let mut units_left = quantity;
+    let mut unit_price = 0;
+
+    for index in order {
+        if units_left == 0 {
+            break;
+        }
+
+        let filled = bids[index].quantity.min(units_left);
+        fills[index] = filled;
+        units_left -= filled;
+        unit_price = bids[index].unit_price;
+    }
+
+    (fills, unit_price)
+}
+
+#[near_bindgen]
+impl Auction {
+    /// add several identical units to an auction as one lot. Buyers bid for a number of units
+    /// and a unit price, all buyers pay the same price when the auction is finished
+    /// (see `units::clear_uniform_price`)
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - representation of an item
+    /// * `quantity` - amount of units
+    /// * `min_bid` - minimal price of one unit. Will be replaced to 1 if 0
+    /// * `metadata` - optional title, description, media, category, condition and extra JSON of the item
+    ///
+    /// return id of the created lot
+    ///
+    /// # Panics
+    ///
+    ///  * quantity is less than 2. Single items are added by `add_item_to_auction`
+    ///  * item can not be added (see `add_item_to_auction`)
+    #[payable]
+    pub fn add_units_to_auction(
+        &mut self,
+        item: &Item,
+        quantity: U64,
+        min_bid: U128,
+        metadata: Option<ItemMetadata>,
+    ) -> ItemHash {
+        if quantity.0 < 2 {
+            AuctionError::TooFewUnits { quantity }.panic()
+        }
+
+        self.list_item(item, min_bid.0, metadata, quantity.0)
+    }
+
+    /// bid for units of a multi-unit lot. Unit bids are not outbid, they are locked in caller's
+    /// balance until the auction is finished. Then filled units are paid with the clearing price
+    /// and the rest is unlocked
+    ///
+    /// # Arguments
+    ///
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `quantity` - amount of units
+    /// * `unit_price` - max price of one unit. Attached deposit is added to caller's free balance
+    /// before `quantity * unit_price` is locked
+    ///
+    /// return index of the bid in the lot's unit bids
+    ///
+    /// # Panics
+    ///
+    ///  * lot does not exist, belongs to the caller or is a single item
+    ///  * quantity is 0 or bigger than the amount of units in the lot
+    ///  * unit price is less than the lot's min bid or the whole bid overflows
+    ///  * free balance must cover the bid
+    ///  * attached deposit must cover storage of a new balance (see `deposit`)
+    ///  * caller can not bid (see `make_bid`)
+    #[payable]
+    pub fn make_unit_bid(&mut self, item_hash: ItemHash, quantity: U64, unit_price: U128) -> U64 {
+        let bidder = env::predecessor_account_id();
+        self.assert_can_bid(&bidder);
+
+        self.deposit_attached(&bidder);
+
+        let supplier_id = self.lots.get(&item_hash).unwrap_or_else(|| {
+            AuctionError::ItemNotFound {
+                item_hash: item_hash.clone(),
+            }
+            .panic()
+        });
+
+        if supplier_id == bidder {
+            AuctionError::OwnItem.panic()
+        }
+
+        let mut supplier = self.suppliers.get(&supplier_id).unwrap();
+        let item = supplier.items.get(&item_hash).unwrap();
+
+        if item.quantity == 1 {
+            AuctionError::SingleUnitItem { item_hash }.panic()
+        }
+        if quantity.0 < 1 || quantity.0 > item.quantity {
+            AuctionError::UnitsOutOfRange {
+                max: U64(item.quantity),
+                quantity,
+            }
+            .panic()
+        }
+        if unit_price.0 < item.min_bid {
+            AuctionError::BelowMinBid {
+                min_bid: U128(item.min_bid),
+                amount: unit_price,
+            }
+            .panic()
+        }
+        if unit_price.0.checked_mul(quantity.0 as Money).is_none() {
+            AuctionError::BidTooBig.panic()
+        }
+
+        let amount = unit_price.0 * quantity.0 as Money;
+
+        self.lock(&bidder, amount);
+        supplier.register_bid(&item_hash);
+
+        let bid = UnitBid {
+            account_id: bidder.clone(),
+            quantity: quantity.0,
+            unit_price: unit_price.0,
+            created_at: env::block_timestamp(),
+            record: self.record_pending_bid(
+                &bidder,
+                &item_hash,
+                amount,
+                BidKind::Units { quantity },
+            ),
+        };
+
+        let mut bids = self.unit_bids.get(&item_hash).unwrap_or_else(|| {
+            Vector::new(StorageKey::UnitBids {
+                lot_hash: env::sha256_array(item_hash.as_bytes()),
+            })
+        });
+        bids.push(&bid);
+
+        // vectors keep their lengths inside themselves so they have to be saved after each push
+        self.unit_bids.insert(&item_hash, &bids);
+
+        U64(bids.len() - 1)
+    }
+
+    /// return unit bids for a multi-unit lot of the current auction, oldest first
+    ///
+    /// # Arguments
+    ///
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `from_index` - index of the first bid. 0 if not set
+    /// * `limit` - max amount of bids. `DEFAULT_PAGE_LIMIT` if not set
+    pub fn get_unit_bids(
+        &self,
+        item_hash: ItemHash,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<UnitBidView> {
+        let bids = match self.unit_bids.get(&item_hash) {
+            Some(bids) => bids,
+            None => return vec![],
+        };
+
+        bids.iter()
+            .skip(from_index.map_or(0, |index| index.0) as usize)
+            .take(limit.map_or(DEFAULT_PAGE_LIMIT, |limit| limit.0) as usize)
+            .map(UnitBidView::from)
+            .collect()
+    }
+}
+
+impl Auction {
+    /// clear every multi-unit lot with a uniform price. Buyers pay the clearing price only for
+    /// filled units, the rest of their bids is unlocked in their balances
+    ///
+    /// return buyers and the clearing price of each lot that has sold at least one unit
+    pub(crate) fn clear_unit_lots(&mut self) -> Vec<(ItemHash, Clearing)> {
+        let lots: Vec<(ItemHash, Vector<UnitBid>)> = self.unit_bids.iter().collect();
+        let mut sales = vec![];
+
+        for (item_hash, mut lot_bids) in lots {
+            let bids = lot_bids.to_vec();
+            lot_bids.clear();
+
+            let quantity = self
+                .get_lot_item(&item_hash)
+                .map_or(0, |item| item.quantity);
+            let (fills, unit_price) = clear_uniform_price(quantity, &bids);
+
+            let mut clearing = Clearing {
+                fills: vec![],
+                unit_price,
+            };
+
+            for (bid, filled) in bids.into_iter().zip(fills) {
+                let unused = bid.amount() - unit_price * filled as Money;
+
+                if unused > 0 {
+                    self.unlock(&bid.account_id, unused);
+                }
+
+                if filled > 0 {
+                    self.settle_bid(&item_hash, bid.record, BidStatus::Won);
+                    clearing.add_fill(bid.account_id, filled);
+                } else {
+                    self.settle_bid(&item_hash, bid.record, BidStatus::Refunded);
+                }
+            }
+
+            if !clearing.fills.is_empty() {
+                sales.push((item_hash, clearing));
+            }
+        }
+
+        self.unit_bids.clear();
+        sales
+    }
+
+    /// money that buyers pay for the multi-unit lot if the auction is finished now. 0 for lots
+    /// without unit bids
+    ///
+    /// # Arguments
+    ///
+    /// * `item_hash` - id of the lot
+    pub(crate) fn unit_lot_total(&self, item_hash: &ItemHash) -> Money {
+        let bids = match self.unit_bids.get(item_hash) {
+            Some(bids) => bids,
+            None => return 0,
+        };
+
+        let quantity = self.get_lot_item(item_hash).map_or(0, |item| item.quantity);
+        let (fills, unit_price) = clear_uniform_price(quantity, &bids.to_vec());
+        let units: u64 = fills.iter().sum();
+
+        unit_price * units as Money
+    }
+
+    /// remove unit bids that have been made after the close time of a candle auction and unlock
+    /// their money
+    ///
+    /// # Arguments
+    ///
+    /// * `close_time` - moment when the candle auction has been closed
+    pub(crate) fn drop_unit_bids_after(&mut self, close_time: u64) {
+        let lots: Vec<(ItemHash, Vector<UnitBid>)> = self.unit_bids.iter().collect();
+
+        for (item_hash, mut bids) in lots {
+            if bids.iter().all(|bid| bid.created_at <= close_time) {
+                continue;
+            }
+
+            let all_bids = bids.to_vec();
+            bids.clear();
+
+            for bid in all_bids {
+                if bid.created_at <= close_time {
+                    bids.push(&bid);
+                } else {
+                    self.unlock(&bid.account_id, bid.amount());
+                    self.settle_bid(&item_hash, bid.record, BidStatus::Refunded);
+                }
+            }
+
+            self.unit_bids.insert(&item_hash, &bids);
+        }
+    }
+
+    /// remove unit bids for the lot and unlock them in their bidders' balances
+    ///
+    /// return bidder and unlocked amount of each bid
+    pub(crate) fn unlock_unit_bids(&mut self, item_hash: &ItemHash) -> Vec<(AccountId, Money)> {
+        let mut bids = match self.unit_bids.remove(item_hash) {
+            Some(bids) => bids,
+            None => return vec![],
+        };
+
+        let unlocked = bids
+            .iter()
+            .map(|bid| {
+                let amount = bid.amount();
+                self.unlock(&bid.account_id, amount);
+                self.settle_bid(item_hash, bid.record, BidStatus::Refunded);
+
+                (bid.account_id, amount)
+            })
+            .collect();
+        bids.clear();
+
+        unlocked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn unit_bid(account_id: &str, quantity: u64, unit_price: Money) -> UnitBid {
+        UnitBid {
+            account_id: AccountId::new_unchecked(account_id.to_string()),
+            quantity,
+            unit_price,
+            created_at: 0,
+            record: 0,
+        }
+    }
+
+    #[test]
+    fn test_uniform_price_is_the_lowest_accepted() {
+        let bids = vec![
+            unit_bid("alice.near", 3, 10),
+            unit_bid("bob.near", 4, 30),
+            unit_bid("carol.near", 5, 20),
+        ];
+
+        // bob gets 4 units, carol gets the last 6, alice gets nothing
+        assert_eq!(clear_uniform_price(10, &bids), (vec![1, 4, 5], 10));
+        assert_eq!(clear_uniform_price(6, &bids), (vec![0, 4, 2], 20));
+    }
+
+    #[test]
+    fn test_equal_prices_are_filled_in_bid_order() {
+        let bids = vec![unit_bid("alice.near", 2, 10), unit_bid("bob.near", 2, 10)];
+
+        assert_eq!(clear_uniform_price(3, &bids), (vec![2, 1], 10));
+    }
+
+    #[test]
+    fn test_undersubscribed_lot() {
+        let bids = vec![unit_bid("alice.near", 2, 15), unit_bid("bob.near", 1, 12)];
+
+        assert_eq!(clear_uniform_price(100, &bids), (vec![2, 1], 12));
+        assert_eq!(clear_uniform_price(100, &[]), (vec![], 0));
+    }
+
+    #[test]
+    fn test_clearing_merges_fills_of_one_buyer() {
+        let buyer = AccountId::new_unchecked("alice.near".to_string());
+        let mut clearing = Clearing {
+            fills: vec![],
+            unit_price: 5,
+        };
+
+        clearing.add_fill(buyer.clone(), 2);
+        clearing.add_fill(buyer.clone(), 3);
+
+        assert_eq!(clearing.fills, vec![(buyer, 5)]);
+        assert_eq!(clearing.units_sold(), 5);
+        assert_eq!(clearing.total(), 25);
+    }
+
+    fn setup_unit_auction(bids: &[(&str, u64, Money)]) -> (TestAuction, ItemHash) {
+        let mut exchange = setup_auction();
+
+        let item_hash = exchange.add_units_to_auction(&"ticket".to_string(), U64(3), U128(5), None);
+
+        for (bidder, quantity, unit_price) in bids {
+            call_contract(bidder, unit_price * *quantity as Money);
+
+            exchange.make_unit_bid(item_hash.clone(), U64(*quantity), U128(*unit_price));
+        }
+
+        (exchange, item_hash)
+    }
+
+    #[test]
+    fn test_multi_unit_lot_uniform_price() {
+        let (mut exchange, item_hash) = setup_unit_auction(&[
+            ("alice.near", 2, 10),
+            ("carol.near", 2, 8),
+            ("dave.near", 1, 6),
+        ]);
+
+        let lot = exchange.get_lot(item_hash.clone()).unwrap();
+        assert_eq!(lot.quantity, U64(3));
+        assert_eq!(exchange.get_unit_bids(item_hash, None, None).len(), 3);
+
+        call_contract("bob.near", 0);
+        exchange.produce_auction();
+
+        // alice gets 2 units, carol gets the last one, both pay 8 for a unit
+        for (bidder, quantity, price, free) in [("alice.near", 2, 16, 4), ("carol.near", 1, 8, 8)] {
+            let bidder = account(bidder);
+            let purchases = exchange.get_purchases(bidder.clone(), None, None);
+
+            assert_eq!(purchases.len(), 1);
+            assert_eq!(purchases[0].quantity, U64(quantity));
+            assert_eq!(purchases[0].price, U128(price));
+            assert_eq!(exchange.get_balance(bidder.clone()).free, U128(free));
+            assert_eq!(exchange.get_balance(bidder).locked, U128(0));
+        }
+
+        let dave = account("dave.near");
+        assert!(exchange.get_purchases(dave.clone(), None, None).is_empty());
+        assert_eq!(exchange.get_balance(dave).free, U128(6));
+
+        let lot = &exchange.get_round(U64(1)).unwrap().lots[0];
+        assert_eq!(lot.units_sold, U64(3));
+        assert_eq!(lot.clearing_price, U128(8));
+        assert_eq!(lot.winner, Some(account("alice.near")));
+
+        let supplier = exchange.get_supplier(get_acc_id(), None, None, None, None);
+        assert_eq!(supplier.sales.len(), 2);
+        assert_eq!(supplier.paid_proceeds, U128(24));
+    }
+
+    #[test]
+    fn test_unit_bid_history() {
+        let (mut exchange, item_hash) = setup_unit_auction(&[
+            ("alice.near", 2, 10),
+            ("carol.near", 2, 8),
+            ("dave.near", 1, 6),
+        ]);
+
+        let history = exchange.get_bid_history(item_hash.clone(), None, None);
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[1].amount, U128(16));
+        assert_eq!(history[1].kind, BidKind::Units { quantity: U64(2) });
+        assert!(history.iter().all(|bid| bid.status == BidStatus::Pending));
+
+        // alice gets 2 units and carol gets 1 unit for 8 each
+        let supplier = exchange.get_supplier(get_acc_id(), None, None, None, None);
+        assert_eq!(supplier.pending_proceeds, U128(24));
+        assert!(supplier.lots[0].has_bids);
+
+        call_contract("bob.near", 0);
+        exchange.produce_auction();
+
+        let statuses: Vec<BidStatus> = exchange
+            .get_bid_history(item_hash, None, None)
+            .into_iter()
+            .map(|bid| bid.status)
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![BidStatus::Won, BidStatus::Won, BidStatus::Refunded]
+        );
+
+        let dave = account("dave.near");
+        assert_eq!(
+            exchange.get_bids_by_account(dave, None, None)[0].status,
+            BidStatus::Refunded
+        );
+    }
+
+    #[test]
+    fn test_undersubscribed_multi_unit_lot() {
+        let (mut exchange, _) = setup_unit_auction(&[("alice.near", 1, 10), ("carol.near", 1, 7)]);
+
+        call_contract("bob.near", 0);
+        exchange.produce_auction();
+
+        let lot = &exchange.get_round(U64(1)).unwrap().lots[0];
+        assert_eq!(lot.units_sold, U64(2));
+        assert_eq!(lot.clearing_price, U128(7));
+
+        let alice = account("alice.near");
+        assert_eq!(exchange.get_balance(alice).free, U128(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "has 3 units. Use make_unit_bid")]
+    fn test_make_bid_for_multi_unit_lot() {
+        let (mut exchange, item_hash) = setup_unit_auction(&[]);
+
+        call_contract("alice.near", 10);
+        exchange.make_bid(&item_hash, U128(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "Quantity must be from 1 to 3. Actual: 4")]
+    fn test_unit_bid_for_too_many_units() {
+        setup_unit_auction(&[("alice.near", 4, 10)]);
+    }
+
+    #[test]
+    fn test_cancel_auction_with_unit_bids() {
+        let (mut exchange, _) = setup_unit_auction(&[("alice.near", 2, 10), ("carol.near", 1, 8)]);
+
+        call_contract("bob.near", 0);
+        assert!(exchange.cancel_auction("fraud".to_string(), None));
+
+        let alice = account("alice.near");
+        assert_eq!(exchange.get_balance(alice).free, U128(20));
+
+        let round = exchange.get_round(U64(1)).unwrap();
+        assert_eq!(round.refunds.len(), 2);
+        assert_eq!(round.lots[0].units_sold, U64(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "too_few_units: Multi-unit lot must contain at least 2 units")]
+    fn test_add_one_unit() {
+        let mut exchange = setup_auction();
+        exchange.add_units_to_auction(&"ticket".to_string(), U64(1), U128(5), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "single_unit_item: Item with hash")]
+    fn test_unit_bid_for_single_item() {
+        let (mut exchange, item_hash) = setup_auction_with_item(10);
+
+        call_contract("carol.near", 10);
+        exchange.make_unit_bid(item_hash, U64(1), U128(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "bid_too_big: Bid is too big")]
+    fn test_overflowing_unit_bid() {
+        let (mut exchange, item_hash) = setup_unit_auction(&[]);
+
+        call_contract("carol.near", 0);
+        exchange.make_unit_bid(item_hash, U64(2), U128(u128::MAX));
+    }
+}
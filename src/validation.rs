@@ -0,0 +1,142 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::errors::AuctionError;
+use crate::views::BidCheckView;
+use crate::{Auction, AuctionExt, ItemHash, Money};
+
+#[near_bindgen]
+impl Auction {
+    /// check if the account can make the bid for the lot right now. Runs the same validation as
+    /// `make_bid` without changing the state. Attached deposit is not known to a view, so the bid
+    /// has to be covered by the account's free balance (see `deposit`)
+    ///
+    /// # Arguments
+    ///
+    /// * `account_id` - bidder
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `amount` - bid
+    pub fn check_bid(
+        &self,
+        account_id: AccountId,
+        item_hash: ItemHash,
+        amount: U128,
+    ) -> BidCheckView {
+        let error = self
+            .check_can_bid(&account_id)
+            .and_then(|_| self.check_place_bid(&account_id, &item_hash, amount.0))
+            .err();
+
+        BidCheckView {
+            ok: error.is_none(),
+            message: error.as_ref().map(|error| error.to_string()),
+            error,
+            min_amount: U128(self.min_acceptable_bid(&item_hash)),
+        }
+    }
+}
+
+impl Auction {
+    /// check that the account can make bids at the moment
+    pub(crate) fn check_can_bid(&self, bidder: &AccountId) -> Result<(), AuctionError> {
+        if self.paused {
+            return Err(AuctionError::ContractPaused);
+        }
+
+        if self.blocklist.contains(bidder) {
+            return Err(AuctionError::AccountBanned {
+                account_id: bidder.clone(),
+            });
+        }
+
+        if !self.is_allowed(bidder.clone()) {
+            return Err(AuctionError::AccountNotAllowed {
+                account_id: bidder.clone(),
+            });
+        }
+
+        if !self.auction_is_open {
+            return Err(AuctionError::AuctionClosed);
+        }
+
+        if let Some(ending) = &self.candle_ending {
+            if env::block_timestamp() > ending.ends_at {
+                return Err(AuctionError::CandleEnded);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// check that the bid for the lot is acceptable and the bidder's free balance covers it
+    ///
+    /// # Arguments
+    ///
+    /// * `bidder` - account that makes the bid
+    /// * `item_hash` - id of the lot (see `supplier::get_lot_id`)
+    /// * `amount` - bid
+    pub(crate) fn check_place_bid(
+        &self,
+        bidder: &AccountId,
+        item_hash: &ItemHash,
+        amount: Money,
+    ) -> Result<(), AuctionError> {
+        let item = match self.lots.get(item_hash) {
+            Some(supplier) if supplier == *bidder => return Err(AuctionError::OwnItem),
+            Some(_) => self.get_lot_item(item_hash).unwrap(),
+            None => {
+                return Err(AuctionError::ItemNotFound {
+                    item_hash: item_hash.clone(),
+                })
+            }
+        };
+
+        if item.quantity != 1 {
+            return Err(AuctionError::MultiUnitItem {
+                item_hash: item_hash.clone(),
+                quantity: U64(item.quantity),
+            });
+        }
+
+        if amount < item.min_bid {
+            return Err(AuctionError::BelowMinBid {
+                min_bid: U128(item.min_bid),
+                amount: U128(amount),
+            });
+        }
+
+        if let Some(bid) = self.items_and_bids.get(item_hash) {
+            if amount <= bid.bid {
+                return Err(AuctionError::BiggerBidExists {
+                    current_bid: U128(bid.bid),
+                });
+            }
+        }
+
+        // the bidder raises his own bid so only the difference is locked
+        let required = amount - self.locked_for_lot(bidder, item_hash);
+        let free = self.escrow.get(bidder).unwrap_or_default().free;
+
+        if free < required {
+            return Err(AuctionError::NotEnoughBalance {
+                free: U128(free),
+                required: U128(required),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// return the smallest bid that the lot accepts now. 0 if the lot does not exist
+    pub(crate) fn min_acceptable_bid(&self, item_hash: &ItemHash) -> Money {
+        let min_bid = match self.get_lot_item(item_hash) {
+            Some(item) => item.min_bid,
+            None => return 0,
+        };
+
+        match self.items_and_bids.get(item_hash) {
+            Some(bid) => min_bid.max(bid.bid + 1),
+            None => min_bid,
+        }
+    }
+}
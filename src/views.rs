@@ -0,0 +1,500 @@
+use near_sdk::json_types::{Base64VecU8, U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::AccountId;
+
+use crate::bundles::BundleBid;
+use crate::candle::CandleEnding;
+use crate::errors::AuctionError;
+use crate::escrow::Escrow;
+use crate::history::{BidKind, BidRecord, BidStatus};
+use crate::metadata::ItemMetadata;
+use crate::moderation::{ModerationAction, ModerationRecord};
+use crate::procurement::ProcurementRequest;
+use crate::rounds::{LotOutcome, ProcurementOutcome, RoundRecord, RoundStatus};
+use crate::sales::{Purchase, Sale};
+use crate::signed_bids::BidKey;
+use crate::supplier::{Item, ItemHash};
+use crate::units::UnitBid;
+use crate::Bid;
+
+/// Representation of a lot for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LotView {
+    pub item_hash: ItemHash,
+    pub content_hash: String,
+    pub item: String,
+    pub metadata: Option<ItemMetadata>,
+    pub supplier: AccountId,
+    pub winner: Option<AccountId>, // account that made the biggest bid. None if there is no bids yet
+    pub current_bid: U128,         // the biggest bid or min bid if there is no bids yet
+    pub has_bids: bool,            // the lot has single, bundle or unit bids
+    pub min_bid: U128,             // min bid for one unit of multi-unit lots
+    pub quantity: U64,             // amount of units. 1 for single items
+    pub created_at: U64,           // nanoseconds
+}
+
+impl LotView {
+    pub fn new(item_hash: ItemHash, item: Item, supplier: &AccountId, bid: Option<Bid>) -> Self {
+        Self {
+            item_hash,
+            content_hash: item.content_hash,
+            item: item.itself,
+            metadata: item.metadata,
+            supplier: supplier.clone(),
+            current_bid: U128(bid.as_ref().map_or(item.min_bid, |bid| bid.bid)),
+            winner: bid.map(|bid| bid.account_id),
+            has_bids: item.has_bids,
+            min_bid: U128(item.min_bid),
+            quantity: U64(item.quantity),
+            created_at: U64(item.created_at),
+        }
+    }
+}
+
+/// Representation of a bid record for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BidRecordView {
+    pub account_id: AccountId,
+    pub item_hash: ItemHash,
+    pub amount: U128,
+    pub timestamp: U64, // nanoseconds
+    pub block_height: U64,
+    pub status: BidStatus,
+    #[serde(flatten)]
+    pub kind: BidKind,
+}
+
+impl From<BidRecord> for BidRecordView {
+    fn from(record: BidRecord) -> Self {
+        Self {
+            account_id: record.account_id,
+            item_hash: record.item_hash,
+            amount: U128(record.amount),
+            timestamp: U64(record.timestamp),
+            block_height: U64(record.block_height),
+            status: record.status,
+            kind: record.kind,
+        }
+    }
+}
+
+/// Representation of a sold item for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SaleView {
+    pub item_hash: ItemHash,
+    pub item: String,
+    pub buyer: AccountId,
+    pub price: U128,
+    pub quantity: U64,
+    pub round: U64,
+    pub sold_at: U64, // nanoseconds
+}
+
+impl From<Sale> for SaleView {
+    fn from(sale: Sale) -> Self {
+        Self {
+            item_hash: sale.item_hash,
+            item: sale.item,
+            buyer: sale.buyer,
+            price: U128(sale.price),
+            quantity: U64(sale.quantity),
+            round: U64(sale.round),
+            sold_at: U64(sale.sold_at),
+        }
+    }
+}
+
+/// Representation of a won item for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PurchaseView {
+    pub item_hash: ItemHash,
+    pub item: String,
+    pub supplier: AccountId,
+    pub price: U128,
+    pub quantity: U64,
+    pub round: U64,
+    pub purchased_at: U64, // nanoseconds
+}
+
+impl From<Purchase> for PurchaseView {
+    fn from(purchase: Purchase) -> Self {
+        Self {
+            item_hash: purchase.item_hash,
+            item: purchase.item,
+            supplier: purchase.supplier,
+            price: U128(purchase.price),
+            quantity: U64(purchase.quantity),
+            round: U64(purchase.round),
+            purchased_at: U64(purchase.purchased_at),
+        }
+    }
+}
+
+/// Supplier's dashboard
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SupplierView {
+    pub account_id: AccountId,
+    pub lots: Vec<LotView>, // requested page of lots of the current auction
+    pub lots_count: U64,
+    pub sales: Vec<SaleView>, // requested page of items sold in all auctions
+    pub sales_count: U64,
+    pub pending_proceeds: U128, // price of all current lots, not only the page, if the auction is finished now
+    pub paid_proceeds: U128,    // money that has been sent to the supplier for sold items
+}
+
+/// Representation of a lot result for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LotOutcomeView {
+    pub item_hash: ItemHash,
+    pub supplier: AccountId,
+    pub winner: Option<AccountId>,
+    pub clearing_price: U128,
+    pub units_sold: U64,
+}
+
+impl From<LotOutcome> for LotOutcomeView {
+    fn from(outcome: LotOutcome) -> Self {
+        Self {
+            item_hash: outcome.item_hash,
+            supplier: outcome.supplier,
+            winner: outcome.winner,
+            clearing_price: U128(outcome.clearing_price),
+            units_sold: U64(outcome.units_sold),
+        }
+    }
+}
+
+/// Representation of a procurement result for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProcurementOutcomeView {
+    pub request_id: ItemHash,
+    pub buyer: AccountId,
+    pub seller: Option<AccountId>,
+    pub price: U128,
+}
+
+impl From<ProcurementOutcome> for ProcurementOutcomeView {
+    fn from(outcome: ProcurementOutcome) -> Self {
+        Self {
+            request_id: outcome.request_id,
+            buyer: outcome.buyer,
+            seller: outcome.seller,
+            price: U128(outcome.price),
+        }
+    }
+}
+
+/// Representation of returned money for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RefundView {
+    pub account_id: AccountId,
+    pub amount: U128,
+}
+
+/// Representation of a completed auction for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RoundView {
+    pub id: U64,
+    #[serde(flatten)]
+    pub status: RoundStatus,
+    pub started_at: U64, // nanoseconds
+    pub ended_at: U64,   // nanoseconds
+    pub lots: Vec<LotOutcomeView>,
+    pub procurements: Vec<ProcurementOutcomeView>,
+    pub refunds: Vec<RefundView>,
+    pub fees: U128,
+}
+
+impl From<RoundRecord> for RoundView {
+    fn from(round: RoundRecord) -> Self {
+        Self {
+            id: U64(round.id),
+            status: round.status,
+            started_at: U64(round.started_at),
+            ended_at: U64(round.ended_at),
+            lots: round.lots.iter().map(LotOutcomeView::from).collect(),
+            procurements: round
+                .procurements
+                .iter()
+                .map(ProcurementOutcomeView::from)
+                .collect(),
+            refunds: round
+                .refunds
+                .iter()
+                .map(|(account_id, amount)| RefundView {
+                    account_id,
+                    amount: U128(amount),
+                })
+                .collect(),
+            fees: U128(round.fees),
+        }
+    }
+}
+
+/// Representation of a bundle bid for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BundleBidView {
+    pub account_id: AccountId,
+    pub items: Vec<ItemHash>,
+    pub amount: U128,
+    pub created_at: U64, // nanoseconds
+}
+
+impl From<BundleBid> for BundleBidView {
+    fn from(bundle: BundleBid) -> Self {
+        Self {
+            account_id: bundle.account_id,
+            items: bundle.items,
+            amount: U128(bundle.amount),
+            created_at: U64(bundle.created_at),
+        }
+    }
+}
+
+/// Representation of a bid for units of a multi-unit lot for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UnitBidView {
+    pub account_id: AccountId,
+    pub quantity: U64,
+    pub unit_price: U128,
+    pub created_at: U64, // nanoseconds
+}
+
+impl From<UnitBid> for UnitBidView {
+    fn from(bid: UnitBid) -> Self {
+        Self {
+            account_id: bid.account_id,
+            quantity: U64(bid.quantity),
+            unit_price: U128(bid.unit_price),
+            created_at: U64(bid.created_at),
+        }
+    }
+}
+
+/// Representation of a procurement request for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProcurementView {
+    pub request_id: ItemHash,
+    pub buyer: AccountId,
+    pub description: String,
+    pub budget: U128,
+    pub best_seller: Option<AccountId>, // seller that made the smallest ask. None if there is no asks yet
+    pub best_ask: Option<U128>,
+    pub created_at: U64, // nanoseconds
+}
+
+impl ProcurementView {
+    pub fn new(request_id: ItemHash, request: ProcurementRequest) -> Self {
+        Self {
+            request_id,
+            buyer: request.buyer,
+            description: request.description,
+            budget: U128(request.budget),
+            best_ask: request.best_ask.as_ref().map(|ask| U128(ask.amount)),
+            best_seller: request.best_ask.map(|ask| ask.account_id),
+            created_at: U64(request.created_at),
+        }
+    }
+}
+
+/// Ending period of a candle auction for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CandleEndingView {
+    pub starts_at: U64, // nanoseconds
+    pub ends_at: U64,   // nanoseconds
+}
+
+impl From<CandleEnding> for CandleEndingView {
+    fn from(ending: CandleEnding) -> Self {
+        Self {
+            starts_at: U64(ending.starts_at),
+            ends_at: U64(ending.ends_at),
+        }
+    }
+}
+
+/// Key that signs bids of an account for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BidKeyView {
+    pub public_key: Base64VecU8,
+    pub last_nonce: U64,
+}
+
+impl From<BidKey> for BidKeyView {
+    fn from(key: BidKey) -> Self {
+        Self {
+            public_key: Base64VecU8(key.public_key),
+            last_nonce: U64(key.last_nonce),
+        }
+    }
+}
+
+/// Result of a dry-run bid validation for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BidCheckView {
+    pub ok: bool,
+    pub error: Option<AuctionError>, // None if the bid can be made
+    pub message: Option<String>,     // `make_bid` panics with the code and this message
+    pub min_amount: U128,            // the smallest bid that the lot accepts now. 0 if no such lot
+}
+
+/// Money that the contract owes and the balance that covers it for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LiabilitiesView {
+    pub locked: U128,       // bids, unit bids, bundle bids and procurement budgets
+    pub withdrawable: U128, // free balances of bidders
+    pub bonds: U128,        // listing bonds of the lots in the current auction
+    pub total: U128,
+    pub balance: U128, // contract balance that is not reserved for storage
+    pub solvent: bool, // does the balance cover all liabilities
+}
+
+/// Bidder's balance for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EscrowView {
+    pub free: U128,
+    pub locked: U128,
+}
+
+impl From<Escrow> for EscrowView {
+    fn from(escrow: Escrow) -> Self {
+        Self {
+            free: U128(escrow.free),
+            locked: U128(escrow.locked),
+        }
+    }
+}
+
+/// Representation of a moderation log entry for frontend
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ModerationRecordView {
+    pub moderator: AccountId,
+    #[serde(flatten)]
+    pub action: ModerationAction,
+    pub reason: String,
+    pub timestamp: U64, // nanoseconds
+}
+
+impl From<ModerationRecord> for ModerationRecordView {
+    fn from(record: ModerationRecord) -> Self {
+        Self {
+            moderator: record.moderator,
+            action: record.action,
+            reason: record.reason,
+            timestamp: U64(record.timestamp),
+        }
+    }
+}
+
+/// Conditions that lots returned by `get_lots` have to satisfy. Empty fields are not checked
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LotFilter {
+    pub supplier: Option<AccountId>,
+    pub category: Option<String>,
+    pub min_price: Option<U128>, // compared with the current bid
+    pub max_price: Option<U128>,
+    pub has_bids: Option<bool>,
+}
+
+impl LotFilter {
+    pub fn matches(&self, lot: &LotView) -> bool {
+        if let Some(supplier) = &self.supplier {
+            if *supplier != lot.supplier {
+                return false;
+            }
+        }
+
+        if let Some(category) = &self.category {
+            let lot_category = lot
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.category.as_ref());
+
+            if lot_category != Some(category) {
+                return false;
+            }
+        }
+
+        if let Some(min_price) = self.min_price {
+            if lot.current_bid.0 < min_price.0 {
+                return false;
+            }
+        }
+
+        if let Some(max_price) = self.max_price {
+            if lot.current_bid.0 > max_price.0 {
+                return false;
+            }
+        }
+
+        if let Some(has_bids) = self.has_bids {
+            if lot.has_bids != has_bids {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_lot() -> LotView {
+        LotView {
+            item_hash: "hash".to_string(),
+            content_hash: "content_hash".to_string(),
+            item: "test_item".to_string(),
+            metadata: None,
+            supplier: AccountId::new_unchecked("bob.near".to_string()),
+            winner: None,
+            current_bid: U128(10),
+            has_bids: false,
+            min_bid: U128(10),
+            quantity: U64(1),
+            created_at: U64(0),
+        }
+    }
+
+    #[test]
+    fn test_empty_filter_matches_everything() {
+        assert!(LotFilter::default().matches(&get_lot()));
+    }
+
+    #[test]
+    fn test_filter_by_price() {
+        let filter = LotFilter {
+            min_price: Some(U128(5)),
+            max_price: Some(U128(9)),
+            ..Default::default()
+        };
+
+        assert!(!filter.matches(&get_lot()));
+    }
+
+    #[test]
+    fn test_filter_by_category() {
+        let filter = LotFilter {
+            category: Some("books".to_string()),
+            ..Default::default()
+        };
+
+        assert!(!filter.matches(&get_lot()), "lot without category matches");
+    }
+
+    #[test]
+    fn test_filter_by_bids() {
+        // bundle and unit bids do not make a winner but the lot has bids
+        let mut lot = get_lot();
+        lot.has_bids = true;
+
+        let filter = LotFilter {
+            has_bids: Some(false),
+            ..Default::default()
+        };
+
+        assert!(!filter.matches(&lot));
+        assert!(filter.matches(&get_lot()));
+    }
+}
@@ -1,7 +1,8 @@
 #!/bin/bash
 # near call contract.msolomodenko.testnet --accountId=contract.msolomodenko.testnet new
 near call contract.msolomodenko.testnet --accountId=contract.msolomodenko.testnet start_new_auction
-near call contract.msolomodenko.testnet --accountId=contract.msolomodenko.testnet add_test_item
-near call contract.msolomodenko.testnet --deposit 1 --accountId=looser.msolomodenko.testnet make_bid '{"item_hash":"68E5EE009D13B901BBB36D3BB47FC59ACA581D6DB141DA0574287495244A9225"}'
-near call contract.msolomodenko.testnet --deposit 2 --accountId=msolomodenko.testnet make_bid '{"item_hash":"68E5EE009D13B901BBB36D3BB47FC59ACA581D6DB141DA0574287495244A9225"}'
+# lot ids are derived from the supplier, round and nonce, so the id is taken from the result of the call
+ITEM_HASH=$(near call contract.msolomodenko.testnet --accountId=contract.msolomodenko.testnet add_test_item | tail -n 1 | tr -d "'")
+near call contract.msolomodenko.testnet --deposit 1 --accountId=looser.msolomodenko.testnet make_bid "{\"item_hash\":\"$ITEM_HASH\",\"amount\":\"1000000000000000000000000\"}"
+near call contract.msolomodenko.testnet --deposit 2 --accountId=msolomodenko.testnet make_bid "{\"item_hash\":\"$ITEM_HASH\",\"amount\":\"2000000000000000000000000\"}"
 near call contract.msolomodenko.testnet --accountId=contract.msolomodenko.testnet produce_auction
@@ -1,17 +1,29 @@
+use ed25519_dalek::{PublicKey, SecretKey};
+use near_sdk::json_types::Base64VecU8;
 use near_units::parse_near;
 use serde_json::json;
 use serde_json::Value;
+use sha2::{Digest, Sha256};
+use workspaces::network::Sandbox;
 use workspaces::prelude::*;
+use workspaces::result::CallExecutionDetails;
+use workspaces::{Account, Contract, Worker};
 
 const SELLER_ACC_ID: &str = "seller";
 const WINNER_ACC_ID: &str = "winner";
 const LOSER_ACC_ID: &str = "loser";
 const WASM_FILEPATH: &str = "./res/auction.wasm";
+// covers storage of a new escrow balance, the rest of it stays free in the balance
+const BALANCE_STORAGE: u128 = parse_near!("0.01 N");
 
 fn yocto_to_token(n: u128) -> f64 {
     n as f64 / 10u128.pow(24) as f64
 }
 
+fn bid_args(item_hash: &str, amount: u128) -> Value {
+    json!({ "item_hash": item_hash, "amount": amount.to_string() })
+}
+
 #[tokio::test]
 async fn test_single_participant() -> anyhow::Result<()> {
     /* #region  init*/
@@ -50,22 +62,17 @@ async fn test_single_participant() -> anyhow::Result<()> {
         }
     );
 
-    seller
+    let item_hash: String = seller
         .call(&worker, contract.id(), "add_item_to_auction")
         .args_json(args_for_sell)?
         .transact()
-        .await?;
-
-    let args_for_bid: Value = json!(
-        {
-            "item_hash":"68E5EE009D13B901BBB36D3BB47FC59ACA581D6DB141DA0574287495244A9225"
-        }
-    );
+        .await?
+        .json()?;
 
     winner
         .call(&worker, contract.id(), "make_bid")
-        .args_json(args_for_bid)?
-        .deposit(parse_near!("10 N"))
+        .args_json(bid_args(&item_hash, parse_near!("10 N")))?
+        .deposit(parse_near!("10 N") + BALANCE_STORAGE)
         .transact()
         .await?;
 
@@ -139,12 +146,6 @@ async fn test_multiple_participants() -> anyhow::Result<()> {
         .await?
         .into_result()?;
 
-    let args_for_bid = json!(
-        {
-            "item_hash":"68E5EE009D13B901BBB36D3BB47FC59ACA581D6DB141DA0574287495244A9225"
-        }
-    );
-
     let args_for_sell = json!(
         {
             "item":"test_item",
@@ -153,23 +154,24 @@ async fn test_multiple_participants() -> anyhow::Result<()> {
     );
     /* #endregion*/
 
-    seller
+    let item_hash: String = seller
         .call(&worker, contract.id(), "add_item_to_auction")
         .args_json(args_for_sell)?
         .transact()
-        .await?;
+        .await?
+        .json()?;
 
     loser
         .call(&worker, contract.id(), "make_bid")
-        .args_json(&args_for_bid)?
-        .deposit(parse_near!("5 N"))
+        .args_json(bid_args(&item_hash, parse_near!("5 N")))?
+        .deposit(parse_near!("5 N") + BALANCE_STORAGE)
         .transact()
         .await?;
 
     winner
         .call(&worker, contract.id(), "make_bid")
-        .args_json(&args_for_bid)?
-        .deposit(parse_near!("10 N"))
+        .args_json(bid_args(&item_hash, parse_near!("10 N")))?
+        .deposit(parse_near!("10 N") + BALANCE_STORAGE)
         .transact()
         .await?;
 
@@ -203,6 +205,13 @@ async fn test_multiple_participants() -> anyhow::Result<()> {
         seller_acc.balance
     );
 
+    // outbid money stays in the contract until the bidder withdraws it
+    loser
+        .call(&worker, contract.id(), "withdraw")
+        .args_json(json!({}))?
+        .transact()
+        .await?;
+
     let loser_acc = loser.view_account(&worker).await?;
 
     assert_eq!(
@@ -252,12 +261,6 @@ async fn two_two_auctions_in_sequence() -> anyhow::Result<()> {
         .await?
         .into_result()?;
 
-    let args_for_bid = json!(
-        {
-            "item_hash":"68E5EE009D13B901BBB36D3BB47FC59ACA581D6DB141DA0574287495244A9225"
-        }
-    );
-
     let args_for_sell = json!(
         {
             "item":"test_item",
@@ -265,23 +268,24 @@ async fn two_two_auctions_in_sequence() -> anyhow::Result<()> {
         }
     );
     /* #endregion*/
-    seller
+    let item_hash: String = seller
         .call(&worker, contract.id(), "add_item_to_auction")
         .args_json(args_for_sell.clone())?
         .transact()
-        .await?;
+        .await?
+        .json()?;
 
     loser
         .call(&worker, contract.id(), "make_bid")
-        .args_json(&args_for_bid)?
-        .deposit(parse_near!("5 N"))
+        .args_json(bid_args(&item_hash, parse_near!("5 N")))?
+        .deposit(parse_near!("5 N") + BALANCE_STORAGE)
         .transact()
         .await?;
 
     winner
         .call(&worker, contract.id(), "make_bid")
-        .args_json(&args_for_bid)?
-        .deposit(parse_near!("10 N"))
+        .args_json(bid_args(&item_hash, parse_near!("10 N")))?
+        .deposit(parse_near!("10 N") + BALANCE_STORAGE)
         .transact()
         .await?;
 
@@ -306,6 +310,13 @@ async fn two_two_auctions_in_sequence() -> anyhow::Result<()> {
         winner_items.len()
     );
 
+    // outbid money stays in the contract until the bidder withdraws it
+    loser
+        .call(&worker, contract.id(), "withdraw")
+        .args_json(json!({}))?
+        .transact()
+        .await?;
+
     let seller_acc = seller.view_account(&worker).await?;
     let loser_acc = loser.view_account(&worker).await?;
 
@@ -328,23 +339,24 @@ async fn two_two_auctions_in_sequence() -> anyhow::Result<()> {
         .transact()
         .await?;
 
-    seller
+    let item_hash: String = seller
         .call(&worker, contract.id(), "add_item_to_auction")
         .args_json(args_for_sell)?
         .transact()
-        .await?;
+        .await?
+        .json()?;
 
     loser
         .call(&worker, contract.id(), "make_bid")
-        .args_json(&args_for_bid)?
-        .deposit(parse_near!("1 N"))
+        .args_json(bid_args(&item_hash, parse_near!("1 N")))?
+        .deposit(parse_near!("1 N") + BALANCE_STORAGE)
         .transact()
         .await?;
 
     winner
         .call(&worker, contract.id(), "make_bid")
-        .args_json(&args_for_bid)?
-        .deposit(parse_near!("5 N"))
+        .args_json(bid_args(&item_hash, parse_near!("5 N")))?
+        .deposit(parse_near!("5 N") + BALANCE_STORAGE)
         .transact()
         .await?;
 
@@ -438,12 +450,6 @@ async fn test_auction_with_two_items() -> anyhow::Result<()> {
         }
     );
 
-    let args_for_bid_1 = json!(
-        {
-                "item_hash":"68E5EE009D13B901BBB36D3BB47FC59ACA581D6DB141DA0574287495244A9225"
-            }
-    );
-
     let args_for_sell_2 = json!(
         {
             "item":"another_test_item",
@@ -451,49 +457,45 @@ async fn test_auction_with_two_items() -> anyhow::Result<()> {
         }
     );
 
-    let args_for_bid_2 = json!(
-        {
-            "item_hash":"AD2AFDA91E9D009272A01459110D14D0AAD7F4648412CE04B2B5E5F322DC527E"
-        }
-    );
-
-    seller_1
+    let item_hash_1: String = seller_1
         .call(&worker, contract.id(), "add_item_to_auction")
         .args_json(args_for_sell_1.clone())?
         .transact()
-        .await?;
+        .await?
+        .json()?;
 
-    seller_2
+    let item_hash_2: String = seller_2
         .call(&worker, contract.id(), "add_item_to_auction")
         .args_json(args_for_sell_2.clone())?
         .transact()
-        .await?;
+        .await?
+        .json()?;
 
     loser
         .call(&worker, contract.id(), "make_bid")
-        .args_json(&args_for_bid_1)?
-        .deposit(parse_near!("5 N"))
+        .args_json(bid_args(&item_hash_1, parse_near!("5 N")))?
+        .deposit(parse_near!("5 N") + BALANCE_STORAGE)
         .transact()
         .await?;
 
     winner
         .call(&worker, contract.id(), "make_bid")
-        .args_json(&args_for_bid_1)?
-        .deposit(parse_near!("10 N"))
+        .args_json(bid_args(&item_hash_1, parse_near!("10 N")))?
+        .deposit(parse_near!("10 N") + BALANCE_STORAGE)
         .transact()
         .await?;
 
     loser
         .call(&worker, contract.id(), "make_bid")
-        .args_json(&args_for_bid_2)?
-        .deposit(parse_near!("5 N"))
+        .args_json(bid_args(&item_hash_2, parse_near!("5 N")))?
+        .deposit(parse_near!("5 N") + BALANCE_STORAGE)
         .transact()
         .await?;
 
     winner
         .call(&worker, contract.id(), "make_bid")
-        .args_json(&args_for_bid_2)?
-        .deposit(parse_near!("10 N"))
+        .args_json(bid_args(&item_hash_2, parse_near!("10 N")))?
+        .deposit(parse_near!("10 N") + BALANCE_STORAGE)
         .transact()
         .await?;
 
@@ -520,6 +522,13 @@ async fn test_auction_with_two_items() -> anyhow::Result<()> {
 
     let seller_acc_1 = seller_1.view_account(&worker).await?;
     let seller_acc_2 = seller_2.view_account(&worker).await?;
+    // outbid money stays in the contract until the bidder withdraws it
+    loser
+        .call(&worker, contract.id(), "withdraw")
+        .args_json(json!({}))?
+        .transact()
+        .await?;
+
     let loser_acc = loser.view_account(&worker).await?;
 
     assert_eq!(
@@ -547,7 +556,7 @@ async fn test_auction_with_two_items() -> anyhow::Result<()> {
 }
 
 #[tokio::test]
-#[should_panic(expected="This item has 2 minimum bid. Actual: 1")]
+#[should_panic(expected="below_min_bid: This item has 2 minimum bid. Actual: 1")]
 async fn bid_less_than_min_bid() {
     let worker = workspaces::sandbox().await.unwrap();
     let wasm = std::fs::read(WASM_FILEPATH).unwrap();
@@ -590,32 +599,28 @@ async fn bid_less_than_min_bid() {
         }
     );
 
-    let args_for_bid = json!(
-        {
-            "item_hash":"68E5EE009D13B901BBB36D3BB47FC59ACA581D6DB141DA0574287495244A9225"
-        }
-    );
-
-    seller
+    let item_hash: String = seller
         .call(&worker, contract.id(), "add_item_to_auction")
-        .args_json(args_for_sell.clone())
+        .args_json(args_for_sell)
         .unwrap()
         .transact()
         .await
+        .unwrap()
+        .json()
         .unwrap();
 
     bidder
         .call(&worker, contract.id(), "make_bid")
-        .args_json(args_for_bid)
+        .args_json(bid_args(&item_hash, 1u128))
         .unwrap()
-        .deposit(1u128)
+        .deposit(1u128 + BALANCE_STORAGE)
         .transact()
         .await
         .unwrap();
 }
 
 #[tokio::test]
-#[should_panic(expected="Item with hash 68E5EE009D13B901BBB36D3BB47FC59ACA581D6DB141DA0574287495244A9225 does not exist")]
+#[should_panic(expected="item_not_found: Item with hash 68E5EE009D13B901BBB36D3BB47FC59ACA581D6DB141DA0574287495244A9225 does not exist")]
 async fn bid_to_non_exists_item() {
     let worker = workspaces::sandbox().await.unwrap();
     let wasm = std::fs::read(WASM_FILEPATH).unwrap();
@@ -645,7 +650,8 @@ async fn bid_to_non_exists_item() {
 
     let args_for_bid = json!(
         {
-            "item_hash":"68E5EE009D13B901BBB36D3BB47FC59ACA581D6DB141DA0574287495244A9225"
+            "item_hash":"68E5EE009D13B901BBB36D3BB47FC59ACA581D6DB141DA0574287495244A9225",
+            "amount": "1"
         }
     );
 
@@ -653,8 +659,645 @@ async fn bid_to_non_exists_item() {
         .call(&worker, contract.id(), "make_bid")
         .args_json(args_for_bid)
         .unwrap()
-        .deposit(1u128)
+        .deposit(1u128 + BALANCE_STORAGE)
         .transact()
         .await
         .unwrap();
 }
+
+#[tokio::test]
+#[should_panic(expected="auction_closed: Auction is closed. Try again later")]
+async fn bid_before_auction_start() {
+    let worker = workspaces::sandbox().await.unwrap();
+    let wasm = std::fs::read(WASM_FILEPATH).unwrap();
+    let contract = worker.dev_deploy(&wasm).await.unwrap();
+
+    let owner = worker.root_account();
+
+    owner
+        .call(&worker, contract.id(), "new")
+        .transact()
+        .await
+        .unwrap();
+
+    let bidder = owner
+        .create_subaccount(&worker, WINNER_ACC_ID)
+        .initial_balance(parse_near!("20 N"))
+        .transact()
+        .await
+        .unwrap()
+        .unwrap();
+
+    bidder
+        .call(&worker, contract.id(), "make_bid")
+        .args_json(bid_args("hash", 1u128))
+        .unwrap()
+        .deposit(1u128 + BALANCE_STORAGE)
+        .transact()
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[should_panic(expected="auction_finished: Auction has already been finished")]
+async fn produce_finished_auction() {
+    let worker = workspaces::sandbox().await.unwrap();
+    let wasm = std::fs::read(WASM_FILEPATH).unwrap();
+    let contract = worker.dev_deploy(&wasm).await.unwrap();
+
+    let owner = worker.root_account();
+
+    owner
+        .call(&worker, contract.id(), "new")
+        .transact()
+        .await
+        .unwrap();
+
+    owner
+        .call(&worker, contract.id(), "produce_auction")
+        .transact()
+        .await
+        .unwrap();
+}
+
+/// Contract with an opened auction. The root account is its owner and admin
+struct Sim {
+    worker: Worker<Sandbox>,
+    contract: Contract,
+    owner: Account,
+}
+
+impl Sim {
+    async fn new() -> anyhow::Result<Self> {
+        let worker = workspaces::sandbox().await?;
+        let wasm = std::fs::read(WASM_FILEPATH)?;
+        let contract = worker.dev_deploy(&wasm).await?;
+        let owner = worker.root_account();
+
+        owner.call(&worker, contract.id(), "new").transact().await?;
+        owner
+            .call(&worker, contract.id(), "start_new_auction")
+            .transact()
+            .await?;
+
+        Ok(Self {
+            worker,
+            contract,
+            owner,
+        })
+    }
+
+    async fn create_account(&self, name: &str) -> anyhow::Result<Account> {
+        self.owner
+            .create_subaccount(&self.worker, name)
+            .initial_balance(parse_near!("20 N"))
+            .transact()
+            .await?
+            .into_result()
+    }
+
+    async fn call(
+        &self,
+        account: &Account,
+        method: &str,
+        args: Value,
+        deposit: u128,
+    ) -> anyhow::Result<CallExecutionDetails> {
+        account
+            .call(&self.worker, self.contract.id(), method)
+            .args_json(args)?
+            .deposit(deposit)
+            .transact()
+            .await
+    }
+
+    /// check that the call fails with the error code
+    async fn assert_error(
+        &self,
+        account: &Account,
+        method: &str,
+        args: Value,
+        deposit: u128,
+        code: &str,
+    ) -> anyhow::Result<()> {
+        match self.call(account, method, args, deposit).await {
+            Ok(_) => anyhow::bail!("{} has not failed with {}", method, code),
+            Err(error) => assert!(
+                error.to_string().contains(&format!("{}: ", code)),
+                "{} has failed with {} instead of {}",
+                method,
+                error,
+                code
+            ),
+        }
+
+        Ok(())
+    }
+
+    async fn add_item(
+        &self,
+        seller: &Account,
+        item: &str,
+        min_bid: u128,
+    ) -> anyhow::Result<String> {
+        let args = json!({ "item": item, "min_bid": min_bid.to_string() });
+
+        self.call(seller, "add_item_to_auction", args, 0)
+            .await?
+            .json()
+    }
+
+    /// open an empty escrow balance of the account, so its first bids do not pay for storage
+    async fn open_balance(&self, account: &Account) -> anyhow::Result<()> {
+        self.call(account, "deposit", json!({}), BALANCE_STORAGE)
+            .await?;
+        self.call(account, "withdraw", json!({}), 0).await?;
+
+        Ok(())
+    }
+
+    async fn now(&self) -> anyhow::Result<u64> {
+        Ok(self.worker.view_latest_block().await?.timestamp())
+    }
+}
+
+fn metadata_args(item: &str, metadata: Value) -> Value {
+    json!({ "item": item, "min_bid": "1", "metadata": metadata })
+}
+
+#[tokio::test]
+async fn admin_error_paths() -> anyhow::Result<()> {
+    let sim = Sim::new().await?;
+    let owner = &sim.owner;
+    let stranger = sim.create_account("stranger").await?;
+
+    for method in ["start_new_auction", "produce_auction", "unpause"] {
+        sim.assert_error(&stranger, method, json!({}), 0, "missing_role")
+            .await?;
+    }
+    sim.assert_error(&stranger, "pause", json!({}), 0, "missing_role")
+        .await?;
+    sim.assert_error(
+        owner,
+        "start_new_auction",
+        json!({}),
+        0,
+        "auction_already_opened",
+    )
+    .await?;
+    sim.assert_error(owner, "unpause", json!({}), 0, "not_paused")
+        .await?;
+
+    sim.call(owner, "pause", json!({}), 0).await?;
+    sim.assert_error(owner, "pause", json!({}), 0, "already_paused")
+        .await?;
+    sim.assert_error(
+        &stranger,
+        "make_bid",
+        bid_args("hash", 1),
+        1,
+        "contract_paused",
+    )
+    .await?;
+    sim.call(owner, "unpause", json!({}), 0).await?;
+
+    let args = json!({ "account_id": stranger.id(), "reason": "spam" });
+    sim.call(owner, "ban_account", args, 0).await?;
+    sim.assert_error(
+        &stranger,
+        "make_bid",
+        bid_args("hash", 1),
+        1,
+        "account_banned",
+    )
+    .await?;
+
+    sim.call(owner, "produce_auction", json!({}), 0).await?;
+    sim.assert_error(owner, "produce_auction", json!({}), 0, "auction_finished")
+        .await?;
+    let args = json!({ "reason": "fraud" });
+    sim.assert_error(owner, "cancel_auction", args, 0, "auction_finished")
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn access_error_paths() -> anyhow::Result<()> {
+    let sim = Sim::new().await?;
+    let owner = &sim.owner;
+    let stranger = sim.create_account("stranger").await?;
+
+    let args = json!({ "invite_code": "secret" });
+    sim.assert_error(&stranger, "join_auction", args, 0, "invite_codes_not_used")
+        .await?;
+
+    let args = json!({
+        "policy": { "kind": "invite_code", "code_hash": Base64VecU8(vec![1, 2, 3]) },
+        "restrict_suppliers": false,
+    });
+    sim.assert_error(
+        owner,
+        "set_access_policy",
+        args,
+        0,
+        "invalid_invite_code_hash",
+    )
+    .await?;
+
+    let code_hash = Base64VecU8(Sha256::digest(b"secret").to_vec());
+    let args = json!({
+        "policy": { "kind": "invite_code", "code_hash": code_hash },
+        "restrict_suppliers": false,
+    });
+    sim.call(owner, "set_access_policy", args, 0).await?;
+
+    let args = json!({ "invite_code": "guess" });
+    sim.assert_error(&stranger, "join_auction", args, 0, "invalid_invite_code")
+        .await?;
+    sim.assert_error(
+        &stranger,
+        "make_bid",
+        bid_args("hash", 1),
+        1,
+        "account_not_allowed",
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancellation_error_paths() -> anyhow::Result<()> {
+    let sim = Sim::new().await?;
+    let owner = &sim.owner;
+    let seller = sim.create_account(SELLER_ACC_ID).await?;
+
+    sim.add_item(&seller, "chair", 1).await?;
+    sim.add_item(&seller, "table", 1).await?;
+
+    let args = json!({ "reason": "fraud", "limit": "1" });
+    let finished: bool = sim.call(owner, "cancel_auction", args, 0).await?.json()?;
+    assert!(!finished, "two lots are cancelled in one batch of one");
+
+    sim.assert_error(
+        owner,
+        "start_new_auction",
+        json!({}),
+        0,
+        "cancellation_in_progress",
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn listing_error_paths() -> anyhow::Result<()> {
+    let sim = Sim::new().await?;
+    let owner = &sim.owner;
+    let seller = sim.create_account(SELLER_ACC_ID).await?;
+    let bidder = sim.create_account(WINNER_ACC_ID).await?;
+
+    sim.call(owner, "set_listing_bond", json!({ "bond": "5" }), 0)
+        .await?;
+    let args = json!({ "item": "chair", "min_bid": "1" });
+    sim.assert_error(
+        &seller,
+        "add_item_to_auction",
+        args,
+        0,
+        "invalid_listing_bond",
+    )
+    .await?;
+    sim.call(owner, "set_listing_bond", json!({ "bond": "0" }), 0)
+        .await?;
+
+    let item_hash = sim.add_item(&seller, "chair", 1).await?;
+    sim.open_balance(&bidder).await?;
+    let args = json!({ "item": "chair", "min_bid": "1" });
+    sim.assert_error(
+        &seller,
+        "add_item_to_auction",
+        args,
+        0,
+        "item_already_added",
+    )
+    .await?;
+
+    let invalid_metadata = [
+        (json!({ "title": "" }), "empty_title"),
+        (json!({ "title": "a".repeat(257) }), "field_too_long"),
+        (
+            json!({ "title": "Lamp", "media": "https://lamp.png" }),
+            "unpaired_media",
+        ),
+        (
+            json!({
+                "title": "Lamp",
+                "media": "https://lamp.png",
+                "media_hash": Base64VecU8(vec![1, 2, 3]),
+            }),
+            "invalid_media_hash",
+        ),
+        (json!({ "title": "Lamp", "extra": "{" }), "invalid_extra"),
+    ];
+    for (metadata, code) in invalid_metadata {
+        let args = metadata_args("lamp", metadata);
+        sim.assert_error(&seller, "add_item_to_auction", args, 0, code)
+            .await?;
+    }
+
+    let args = json!({ "item": "spoons", "quantity": "1", "min_bid": "1" });
+    sim.assert_error(&seller, "add_units_to_auction", args, 0, "too_few_units")
+        .await?;
+
+    let args = json!({ "item_hash": item_hash });
+    sim.assert_error(&bidder, "remove_item", args, 0, "not_supplier")
+        .await?;
+    let args = json!({ "item_hash": "hash" });
+    sim.assert_error(&seller, "remove_item", args, 0, "item_not_found")
+        .await?;
+    let args = json!({ "item_hash": "hash", "reason": "spam" });
+    sim.assert_error(owner, "delist_item", args, 0, "item_not_found")
+        .await?;
+
+    sim.call(&bidder, "make_bid", bid_args(&item_hash, 1), 1)
+        .await?;
+    let args = json!({ "item_hash": item_hash });
+    sim.assert_error(&seller, "remove_item", args, 0, "item_has_bids")
+        .await?;
+
+    sim.call(owner, "produce_auction", json!({}), 0).await?;
+    let args = json!({ "item": "lamp", "min_bid": "1" });
+    sim.assert_error(&seller, "add_item_to_auction", args, 0, "auction_closed")
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn bid_error_paths() -> anyhow::Result<()> {
+    let sim = Sim::new().await?;
+    let seller = sim.create_account(SELLER_ACC_ID).await?;
+    let winner = sim.create_account(WINNER_ACC_ID).await?;
+    let loser = sim.create_account(LOSER_ACC_ID).await?;
+    for account in [&seller, &winner, &loser] {
+        sim.open_balance(account).await?;
+    }
+
+    let chair = sim.add_item(&seller, "chair", 10).await?;
+    let table = sim.add_item(&seller, "table", 10).await?;
+    let args = json!({ "item": "spoons", "quantity": "5", "min_bid": "10" });
+    let spoons: String = sim
+        .call(&seller, "add_units_to_auction", args, 0)
+        .await?
+        .json()?;
+
+    let failing_bids = [
+        (&seller, bid_args(&chair, 10), 10, "own_item"),
+        (&winner, bid_args("hash", 10), 10, "item_not_found"),
+        (&winner, bid_args(&chair, 5), 5, "below_min_bid"),
+        (&winner, bid_args(&spoons, 10), 10, "multi_unit_item"),
+        (&winner, bid_args(&chair, 20), 10, "not_enough_balance"),
+    ];
+    for (bidder, args, deposit, code) in failing_bids {
+        sim.assert_error(bidder, "make_bid", args, deposit, code)
+            .await?;
+    }
+
+    sim.call(&winner, "make_bid", bid_args(&chair, 15), 15)
+        .await?;
+    sim.assert_error(
+        &loser,
+        "make_bid",
+        bid_args(&chair, 15),
+        15,
+        "bigger_bid_exists",
+    )
+    .await?;
+
+    let failing_bids = [
+        (json!([]), 0, "empty_bids"),
+        (json!([[table, "10"], [table, "11"]]), 21, "duplicate_item"),
+        (
+            json!([[table, "10"], [chair, "20"]]),
+            10,
+            "not_enough_balance_for_bids",
+        ),
+    ];
+    for (bids, deposit, code) in failing_bids {
+        let args = json!({ "bids": bids });
+        sim.assert_error(&loser, "make_bids", args, deposit, code)
+            .await?;
+    }
+
+    let unit_bid_args = |item_hash: &str, quantity: u64, unit_price: u128| {
+        json!({
+            "item_hash": item_hash,
+            "quantity": quantity.to_string(),
+            "unit_price": unit_price.to_string(),
+        })
+    };
+    let failing_bids = [
+        (&winner, unit_bid_args("hash", 1, 10), "item_not_found"),
+        (&seller, unit_bid_args(&spoons, 1, 10), "own_item"),
+        (&winner, unit_bid_args(&chair, 1, 10), "single_unit_item"),
+        (&winner, unit_bid_args(&spoons, 0, 10), "units_out_of_range"),
+        (&winner, unit_bid_args(&spoons, 6, 10), "units_out_of_range"),
+        (&winner, unit_bid_args(&spoons, 1, 5), "below_min_bid"),
+        (&winner, unit_bid_args(&spoons, 2, u128::MAX), "bid_too_big"),
+    ];
+    for (bidder, args, code) in failing_bids {
+        sim.assert_error(bidder, "make_unit_bid", args, 10, code)
+            .await?;
+    }
+
+    let failing_bids = [
+        (&loser, json!([chair]), "bundle_too_small"),
+        (&loser, json!([chair, chair]), "duplicate_item"),
+        (&loser, json!([chair, "hash"]), "item_not_found"),
+        (&seller, json!([chair, table]), "own_item"),
+        (&loser, json!([chair, spoons]), "multi_unit_bundle_item"),
+    ];
+    for (bidder, items, code) in failing_bids {
+        let args = json!({ "items": items, "amount": "30" });
+        sim.assert_error(bidder, "make_bundle_bid", args, 30, code)
+            .await?;
+    }
+    let args = json!({ "items": [chair, table], "amount": "15" });
+    sim.assert_error(&loser, "make_bundle_bid", args, 15, "bundle_below_min_bids")
+        .await?;
+
+    let args = json!({ "starts_at": "1", "ends_at": "2" });
+    sim.assert_error(
+        &sim.owner,
+        "set_candle_ending",
+        args,
+        0,
+        "candle_ending_after_bids",
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn escrow_and_procurement_error_paths() -> anyhow::Result<()> {
+    let sim = Sim::new().await?;
+    let buyer = sim.create_account(WINNER_ACC_ID).await?;
+    let seller = sim.create_account(SELLER_ACC_ID).await?;
+
+    sim.assert_error(&buyer, "deposit", json!({}), 0, "no_deposit")
+        .await?;
+    sim.assert_error(&buyer, "deposit", json!({}), 1, "storage_not_covered")
+        .await?;
+    let args = json!({ "amount": "1" });
+    sim.assert_error(&buyer, "withdraw", args, 0, "not_enough_balance")
+        .await?;
+    sim.open_balance(&buyer).await?;
+
+    let args = json!({ "description": "", "budget": "100" });
+    sim.assert_error(
+        &buyer,
+        "create_procurement",
+        args,
+        100,
+        "invalid_description",
+    )
+    .await?;
+    let args = json!({ "description": "100 chairs", "budget": "0" });
+    sim.assert_error(&buyer, "create_procurement", args, 0, "zero_budget")
+        .await?;
+
+    let args = json!({ "description": "100 chairs", "budget": "100" });
+    let request_id: String = sim
+        .call(&buyer, "create_procurement", args, 100)
+        .await?
+        .json()?;
+
+    let ask_args = |request_id: &str, amount: u128| json!({ "request_id": request_id, "amount": amount.to_string() });
+    sim.assert_error(
+        &seller,
+        "make_ask",
+        ask_args(&request_id, 60),
+        0,
+        "storage_not_covered",
+    )
+    .await?;
+    sim.call(
+        &seller,
+        "make_ask",
+        ask_args(&request_id, 60),
+        parse_near!("0.01 N"),
+    )
+    .await?;
+
+    let failing_asks = [
+        (&seller, ask_args("hash", 50), "procurement_not_found"),
+        (&buyer, ask_args(&request_id, 50), "own_request"),
+        (&seller, ask_args(&request_id, 0), "zero_ask"),
+        (&seller, ask_args(&request_id, 101), "ask_above_budget"),
+        (&seller, ask_args(&request_id, 60), "smaller_ask_exists"),
+    ];
+    for (account, args, code) in failing_asks {
+        sim.assert_error(account, "make_ask", args, 0, code).await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn signed_bid_error_paths() -> anyhow::Result<()> {
+    let sim = Sim::new().await?;
+    let seller = sim.create_account(SELLER_ACC_ID).await?;
+    let bidder = sim.create_account(WINNER_ACC_ID).await?;
+    let relayer = sim.create_account(LOSER_ACC_ID).await?;
+
+    let item_hash = sim.add_item(&seller, "chair", 1).await?;
+
+    let args = json!({ "public_key": Base64VecU8(vec![1, 2, 3]) });
+    sim.assert_error(&bidder, "register_bid_key", args, 0, "invalid_public_key")
+        .await?;
+
+    let secret_key = SecretKey::from_bytes(&[7; 32])?;
+    let public_key = Base64VecU8(PublicKey::from(&secret_key).to_bytes().to_vec());
+    let args = json!({ "public_key": public_key });
+    sim.assert_error(&bidder, "register_bid_key", args, 0, "storage_not_covered")
+        .await?;
+
+    let signed_bid_args = |nonce: u64, expires_at: u64| {
+        json!({
+            "bidder": bidder.id(),
+            "item_hash": item_hash,
+            "amount": "10",
+            "nonce": nonce.to_string(),
+            "expires_at": expires_at.to_string(),
+            "signature": Base64VecU8(vec![0; 64]),
+        })
+    };
+    sim.assert_error(
+        &relayer,
+        "make_bid_signed",
+        signed_bid_args(1, u64::MAX),
+        0,
+        "bid_key_not_found",
+    )
+    .await?;
+
+    let args = json!({ "public_key": public_key });
+    sim.call(&bidder, "register_bid_key", args, parse_near!("0.01 N"))
+        .await?;
+
+    let failing_bids = [
+        (signed_bid_args(1, 0), "signed_bid_expired"),
+        (signed_bid_args(0, u64::MAX), "invalid_nonce"),
+        (signed_bid_args(1, u64::MAX), "invalid_signature"),
+    ];
+    for (args, code) in failing_bids {
+        sim.assert_error(&relayer, "make_bid_signed", args, 0, code)
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn candle_error_paths() -> anyhow::Result<()> {
+    let sim = Sim::new().await?;
+    let owner = &sim.owner;
+    let seller = sim.create_account(SELLER_ACC_ID).await?;
+    let bidder = sim.create_account(WINNER_ACC_ID).await?;
+
+    let item_hash = sim.add_item(&seller, "chair", 1).await?;
+
+    let second = 1_000_000_000;
+    let now = sim.now().await?;
+    let args = json!({
+        "starts_at": (now + 20 * second).to_string(),
+        "ends_at": (now + 10 * second).to_string(),
+    });
+    sim.assert_error(owner, "set_candle_ending", args, 0, "invalid_candle_ending")
+        .await?;
+
+    let args = json!({
+        "starts_at": now.to_string(),
+        "ends_at": (now + 10 * second).to_string(),
+    });
+    sim.call(owner, "set_candle_ending", args, 0).await?;
+    sim.assert_error(owner, "produce_auction", json!({}), 0, "candle_not_over")
+        .await?;
+
+    // blocks of the sandbox are produced about once a second
+    sim.worker.fast_forward(100).await?;
+    sim.assert_error(
+        &bidder,
+        "make_bid",
+        bid_args(&item_hash, 1),
+        1,
+        "candle_ended",
+    )
+    .await?;
+
+    Ok(())
+}